@@ -1,26 +1,70 @@
 mod ytdlp;
 
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager, State};
-use tokio::sync::Mutex;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use ytdlp::downloader::{AudioFormat, DownloadMode, DownloadOptions, Downloader, VideoContainer, VideoInfo, VideoQuality};
-use ytdlp::manager::YtDlpManager;
-use ytdlp::updater::{UpdateStatus, Updater};
+use ytdlp::convert::{BatchConvertProgress, BatchConvertResult, ConvertTarget};
+use ytdlp::disk::DiskSpace;
+use ytdlp::duplicates::{DuplicateGroup, DuplicateScanProgress};
+use ytdlp::downloader::{AudioFormat, DownloadMode, DownloadOptions, DownloadProgress, Downloader, DownloaderError, FormatFilesize, FormatInfo, PlaylistEntry, SimulatedFile, VideoContainer, VideoInfo, VideoQuality};
+use ytdlp::history::HistoryEntry;
+use ytdlp::manager::{FfmpegInstallGuidance, InstalledVersion, YtDlpManager};
+use ytdlp::proxy::ProxyTestResult;
+use ytdlp::queue::{DownloadQueue, QueueState};
+use ytdlp::settings::Settings;
+use ytdlp::sponsorblock::SponsorBlockCategory;
+use ytdlp::updater::{SelfUpdateResult, UpdateStatus, Updater, UpdaterError};
 
 // App state
 pub struct AppState {
     downloader: Arc<Mutex<Option<Downloader>>>,
     updater: Arc<Mutex<Option<Updater>>>,
+    duplicate_scan_cancel: Arc<AtomicBool>,
+    queue: Arc<DownloadQueue>,
+    settings: Arc<Mutex<Settings>>,
+    progress_acks: Arc<std::sync::Mutex<HashMap<String, Arc<Notify>>>>,
+    binary_download_cancel: Arc<std::sync::Mutex<Option<CancellationToken>>>,
+    batch_convert_cancel: Arc<std::sync::Mutex<Option<CancellationToken>>>,
+    /// Running `start_playlist_enumeration` requests by id.
+    playlist_enumerations: Arc<std::sync::Mutex<HashMap<String, CancellationToken>>>,
+    /// Running `get_stream_to_file` downloads by URL.
+    streams: Arc<std::sync::Mutex<HashMap<String, CancellationToken>>>,
+    extractor_warmed: Arc<AtomicBool>,
+    /// Shared HTTP client for requests made outside yt-dlp.
+    http: reqwest::Client,
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        let settings = Settings::load().unwrap_or_default();
+        YtDlpManager::set_active_version(settings.active_ytdlp_version.clone());
+        let queue = DownloadQueue::new();
+        if let Some(max) = settings.max_concurrent_downloads {
+            queue.set_max_concurrent(max);
+        }
+
         Self {
             downloader: Arc::new(Mutex::new(None)),
             updater: Arc::new(Mutex::new(None)),
+            duplicate_scan_cancel: Arc::new(AtomicBool::new(false)),
+            queue: Arc::new(queue),
+            settings: Arc::new(Mutex::new(settings)),
+            progress_acks: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            binary_download_cancel: Arc::new(std::sync::Mutex::new(None)),
+            batch_convert_cancel: Arc::new(std::sync::Mutex::new(None)),
+            playlist_enumerations: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            streams: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            extractor_warmed: Arc::new(AtomicBool::new(false)),
+            http: reqwest::Client::new(),
         }
     }
 }
@@ -29,19 +73,20 @@ impl Default for AppState {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppStatus {
     pub ytdlp_installed: bool,
+    /// yt-dlp comes from PATH (e.g. a package manager) rather than the app's bin dir.
+    pub using_system_ytdlp: bool,
     pub ffmpeg_installed: bool,
     pub ytdlp_version: Option<String>,
     pub default_download_dir: String,
+    /// Saved user settings, so the UI can prefill its defaults.
+    pub settings: Settings,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DownloadProgressEvent {
     pub id: String,
-    pub status: String,
-    pub percentage: Option<f64>,
-    pub speed: Option<String>,
-    pub eta: Option<String>,
-    pub filename: Option<String>,
+    #[serde(flatten)]
+    pub progress: DownloadProgress,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -74,10 +119,12 @@ async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, String>
 
     {
         let state_clone = state.updater.clone();
+        let settings = state.settings.lock().await.clone();
         tokio::spawn(async move {
             let mut updater_guard = state_clone.lock().await;
             if updater_guard.is_none() {
-                if let Ok(updater) = Updater::new() {
+                if let Ok(mut updater) = new_updater(settings.release_proxy.as_deref()) {
+                    updater.set_mirror_base(settings.release_mirror_base);
                     *updater_guard = Some(updater);
                 }
             }
@@ -86,11 +133,13 @@ async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, String>
 
     Ok(AppStatus {
         ytdlp_installed,
+        using_system_ytdlp: manager.is_using_system_ytdlp(),
         ffmpeg_installed,
         ytdlp_version: None, // Load version lazily to speed up startup
         default_download_dir: YtDlpManager::get_default_download_dir()
             .to_string_lossy()
             .to_string(),
+        settings: state.settings.lock().await.clone(),
     })
 }
 
@@ -104,6 +153,24 @@ async fn get_ytdlp_version() -> Result<Option<String>, String> {
     }
 }
 
+#[tauri::command]
+async fn verify_ytdlp() -> Result<String, String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager.verify_ytdlp().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn ytdlp_supports_option(flag: String) -> Result<bool, String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager.supports_option(&flag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn fix_permissions() -> Result<Vec<String>, String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager.fix_permissions().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn check_update(state: State<'_, AppState>) -> Result<UpdateStatus, String> {
     let updater_guard = state.updater.lock().await;
@@ -117,17 +184,28 @@ async fn download_ytdlp(app: AppHandle, state: State<'_, AppState>) -> Result<St
     let updater_guard = state.updater.lock().await;
     let updater = updater_guard.as_ref().ok_or("Updater not initialized")?;
 
+    let cancel = CancellationToken::new();
+    *state.binary_download_cancel.lock().unwrap() = Some(cancel.clone());
+
     let app_clone = app.clone();
-    let path = updater
-        .download_ytdlp(move |progress| {
+    let result = updater
+        .download_ytdlp(cancel, move |progress| {
             let _ = app_clone.emit("ytdlp-download-progress", YtDlpDownloadProgress {
                 downloaded: progress.downloaded,
                 total: progress.total,
                 percentage: progress.percentage,
             });
         })
-        .await
-        .map_err(|e| e.to_string())?;
+        .await;
+    state.binary_download_cancel.lock().unwrap().take();
+
+    let path = match result {
+        Err(UpdaterError::Cancelled) => {
+            let _ = app.emit("ytdlp-download-cancelled", ());
+            return Err(UpdaterError::Cancelled.to_string());
+        }
+        result => result.map_err(|e| e.to_string())?,
+    };
 
     // Reinitialize downloader after installation
     drop(updater_guard);
@@ -139,37 +217,284 @@ async fn download_ytdlp(app: AppHandle, state: State<'_, AppState>) -> Result<St
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Updates yt-dlp with its own `-U`, falling back to downloading the latest
+/// binary (`download_ytdlp`) when that fails, e.g. on a read-only install.
+#[tauri::command]
+async fn self_update_ytdlp(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SelfUpdateResult, String> {
+    let updater_guard = state.updater.lock().await;
+    let updater = updater_guard.as_ref().ok_or("Updater not initialized")?;
+    let result = updater.self_update().await;
+    drop(updater_guard);
+
+    match result {
+        Ok(result) => Ok(result),
+        // Only a failed `-U` (read-only install, permissions) falls back to a
+        // fresh download; yt-dlp from PATH or a system package is left alone.
+        Err(e @ UpdaterError::NoManagedBinary) => Err(e.to_string()),
+        Err(_) => {
+            download_ytdlp(app, state).await?;
+            let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+            let version = manager.get_ytdlp_version().map_err(|e| e.to_string())?;
+            Ok(SelfUpdateResult {
+                updated: true,
+                version,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FfmpegDownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub percentage: Option<f64>,
+}
+
+/// Downloads and installs ffmpeg, emitting `ffmpeg-download-progress`.
+/// `cancel_ytdlp_download` aborts it like any other binary download.
+#[tauri::command]
+async fn download_ffmpeg(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let updater_guard = state.updater.lock().await;
+    let updater = updater_guard.as_ref().ok_or("Updater not initialized")?;
+
+    let cancel = CancellationToken::new();
+    *state.binary_download_cancel.lock().unwrap() = Some(cancel.clone());
+
+    let app_clone = app.clone();
+    let result = updater
+        .download_ffmpeg(cancel, move |progress| {
+            let _ = app_clone.emit("ffmpeg-download-progress", FfmpegDownloadProgress {
+                downloaded: progress.downloaded,
+                total: progress.total,
+                percentage: progress.percentage,
+            });
+        })
+        .await;
+    state.binary_download_cancel.lock().unwrap().take();
+
+    match result {
+        Err(UpdaterError::Cancelled) => {
+            let _ = app.emit("ffmpeg-download-cancelled", ());
+            Err(UpdaterError::Cancelled.to_string())
+        }
+        result => result
+            .map(|path| path.to_string_lossy().to_string())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+#[tauri::command]
+fn list_installed_versions() -> Result<Vec<InstalledVersion>, String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager.list_installed_versions().map_err(|e| e.to_string())
+}
+
+/// Downloads release `tag` as an additional versioned binary. Progress is
+/// reported like `download_ytdlp`, and `cancel_ytdlp_download` aborts it.
+#[tauri::command]
+async fn install_ytdlp_version(
+    app: AppHandle,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    if !ytdlp::manager::is_valid_version_tag(&tag) {
+        return Err(format!("Invalid yt-dlp version tag: {}", tag));
+    }
+
+    let updater_guard = state.updater.lock().await;
+    let updater = updater_guard.as_ref().ok_or("Updater not initialized")?;
+
+    let cancel = CancellationToken::new();
+    *state.binary_download_cancel.lock().unwrap() = Some(cancel.clone());
+
+    let app_clone = app.clone();
+    let result = updater
+        .download_ytdlp_version(&tag, cancel, move |progress| {
+            let _ = app_clone.emit("ytdlp-download-progress", YtDlpDownloadProgress {
+                downloaded: progress.downloaded,
+                total: progress.total,
+                percentage: progress.percentage,
+            });
+        })
+        .await;
+    state.binary_download_cancel.lock().unwrap().take();
+
+    match result {
+        Err(UpdaterError::Cancelled) => {
+            let _ = app.emit("ytdlp-download-cancelled", ());
+            Err(UpdaterError::Cancelled.to_string())
+        }
+        result => result
+            .map(|path| path.to_string_lossy().to_string())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Selects the yt-dlp binary used from now on; `None` goes back to the default one.
+#[tauri::command]
+async fn switch_version(tag: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager
+        .switch_version(tag.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    let mut settings = state.settings.lock().await;
+    settings.active_ytdlp_version = tag;
+    settings.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn remove_version(tag: String) -> Result<(), String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    manager.remove_version(&tag).map_err(|e| e.to_string())
+}
+
+/// Aborts an in-progress yt-dlp binary download. Returns false if nothing was downloading.
+#[tauri::command]
+fn cancel_ytdlp_download(state: State<'_, AppState>) -> bool {
+    match state.binary_download_cancel.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Network options for info fetches like `get_video_info`, mirroring the ones
+/// on `StartDownloadRequest` so the fetch sees what the download will.
+#[derive(Debug, Default, Deserialize)]
+struct InfoNetworkOptions {
+    cookies_from_browser: Option<String>,
+    cookies_file: Option<PathBuf>,
+    proxy: Option<String>,
+    #[serde(default)]
+    geo_bypass: bool,
+    geo_bypass_country: Option<String>,
+    http_headers: Option<HashMap<String, String>>,
+    user_agent: Option<String>,
+}
+
+impl InfoNetworkOptions {
+    /// Validated yt-dlp network arguments for these options.
+    fn into_args(self) -> Result<Vec<String>, String> {
+        let mut args = ytdlp::downloader::cookie_args(
+            self.cookies_from_browser.as_deref(),
+            self.cookies_file.as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
+        args.extend(
+            ytdlp::downloader::proxy_args(self.proxy.as_deref()).map_err(|e| e.to_string())?,
+        );
+        args.extend(
+            ytdlp::downloader::geo_bypass_args(self.geo_bypass, self.geo_bypass_country.as_deref())
+                .map_err(|e| e.to_string())?,
+        );
+        args.extend(
+            ytdlp::downloader::header_args(
+                header_pairs(self.http_headers).as_deref(),
+                self.user_agent.as_deref(),
+            )
+            .map_err(|e| e.to_string())?,
+        );
+        Ok(args)
+    }
+}
+
 #[tauri::command]
-async fn get_video_info(url: String, state: State<'_, AppState>) -> Result<VideoInfo, String> {
+async fn get_video_info(
+    url: String,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<VideoInfo, String> {
+    let extra_args = network.unwrap_or_default().into_args()?;
+
     let downloader_guard = state.downloader.lock().await;
     let downloader = downloader_guard
         .as_ref()
         .ok_or("Downloader not initialized. Please install yt-dlp first.")?;
 
     downloader
-        .get_video_info(&url)
+        .get_video_info(&url, &extra_args)
         .await
         .map_err(|e| e.to_string())
 }
 
-#[derive(Debug, Deserialize)]
-pub struct StartDownloadRequest {
-    pub url: String,
-    pub output_dir: String,
-    // 비디오 옵션
-    pub video_quality: Option<String>,
-    pub video_container: Option<String>,
-    // 오디오 옵션
-    pub audio_format: Option<String>,
-    // 기존
-    pub embed_subs: bool,
-    pub playlist_items: Option<Vec<usize>>,
+/// Recent info-fetch latency per extractor, for telling a slow site from an
+/// outdated yt-dlp.
+#[tauri::command]
+async fn get_extractor_timings(
+    state: State<'_, AppState>,
+) -> Result<Vec<ytdlp::downloader::ExtractorTiming>, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?;
+    Ok(downloader.extractor_timings())
 }
 
 #[tauri::command]
-async fn start_download(
-    app: AppHandle,
-    request: StartDownloadRequest,
+async fn get_format_filesize(
+    url: String,
+    format_id: String,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<FormatFilesize, String> {
+    let extra_args = network.unwrap_or_default().into_args()?;
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    downloader
+        .get_format_filesize(&url, &format_id, &extra_args)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_formats(
+    url: String,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<FormatInfo>, String> {
+    let extra_args = network.unwrap_or_default().into_args()?;
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    downloader
+        .list_formats(&url, &extra_args)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Thumbnail image bytes, fetched by the backend so the webview never loads
+/// the remote URL.
+#[tauri::command]
+async fn fetch_thumbnail(
+    url: String,
+    video_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<u8>, String> {
+    ytdlp::thumbnails::fetch_thumbnail(&state.http, &url, video_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn capture_frame(
+    url: String,
+    timestamp: String,
+    output_dir: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
     let downloader_guard = state.downloader.lock().await;
@@ -177,84 +502,975 @@ async fn start_download(
         .as_ref()
         .ok_or("Downloader not initialized. Please install yt-dlp first.")?
         .clone();
+    drop(downloader_guard);
 
-    let mode = if let Some(audio_fmt) = request.audio_format {
-        // 오디오 모드
-        let format = match audio_fmt.as_str() {
-            "mp3" => AudioFormat::Mp3,
-            "m4a" => AudioFormat::M4a,
-            "aac" => AudioFormat::Aac,
-            "flac" => AudioFormat::Flac,
-            "wav" => AudioFormat::Wav,
-            _ => AudioFormat::Mp3, // 기본값
-        };
-        DownloadMode::Audio { format }
-    } else {
-        // 비디오 모드
-        let quality = match request.video_quality.as_deref() {
-            Some("720p") => VideoQuality::P720,
-            Some("480p") => VideoQuality::P480,
-            _ => VideoQuality::Best, // 기본값
-        };
-        let container = match request.video_container.as_deref() {
-            Some("mkv") => VideoContainer::Mkv,
-            Some("webm") => VideoContainer::Webm,
-            _ => VideoContainer::Mp4, // 기본값
-        };
-        DownloadMode::Video { quality, container }
-    };
+    downloader
+        .capture_frame(&url, &timestamp, &output_dir)
+        .await
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
 
-    let options = DownloadOptions {
-        url: request.url,
-        output_dir: request.output_dir,
-        mode,
-        embed_subs: request.embed_subs,
-        playlist_items: request.playlist_items,
-    };
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistEnumerationProgressEvent {
+    pub id: String,
+    pub index: usize,
+    pub entry: PlaylistEntry,
+}
 
-    let download_id = Uuid::new_v4().to_string();
-    let download_id_for_progress = download_id.clone();
-    let download_id_for_error = download_id.clone();
-    let app_for_progress = app.clone();
-    let app_for_error = app.clone();
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistEnumerationCompleteEvent {
+    pub id: String,
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+/// Full metadata (duration, uploader) for selected playlist entries, which a
+/// flat listing often lacks.
+#[tauri::command]
+async fn get_playlist_details(
+    url: String,
+    indices: Vec<usize>,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PlaylistEntry>, String> {
+    if indices.contains(&0) {
+        return Err("Playlist indices start at 1".to_string());
+    }
+    let extra_args = network.unwrap_or_default().into_args()?;
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    downloader
+        .get_playlist_details(&url, &indices, &extra_args)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn start_playlist_enumeration(
+    app: AppHandle,
+    url: String,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let extra_args = network.unwrap_or_default().into_args()?;
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let request_id = Uuid::new_v4().to_string();
+    let id_for_entries = request_id.clone();
+    let id_for_complete = request_id.clone();
+    let app_for_entries = app.clone();
+    let cancel = CancellationToken::new();
+    let enumerations = state.playlist_enumerations.clone();
+    enumerations
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), cancel.clone());
 
-    // Spawn download task in background and return immediately
     tokio::spawn(async move {
         let result = downloader
-            .download(&options, move |progress| {
-                let _ = app_for_progress.emit(
-                    "download-progress",
-                    DownloadProgressEvent {
-                        id: download_id_for_progress.clone(),
-                        status: progress.status.clone(),
-                        percentage: progress.percentage,
-                        speed: progress.speed.clone(),
-                        eta: progress.eta.clone(),
-                        filename: progress.filename.clone(),
+            .enumerate_playlist(&url, &extra_args, cancel, move |index, entry| {
+                let _ = app_for_entries.emit(
+                    "playlist-enumeration-progress",
+                    PlaylistEnumerationProgressEvent {
+                        id: id_for_entries.clone(),
+                        index,
+                        entry,
                     },
                 );
             })
             .await;
+        enumerations.lock().unwrap().remove(&id_for_complete);
 
-        if let Err(e) = result {
-            let _ = app_for_error.emit(
+        let (total, error) = match result {
+            Ok(total) => (total, None),
+            Err(e) => (0, Some(e.to_string())),
+        };
+        let _ = app.emit(
+            "playlist-enumeration-complete",
+            PlaylistEnumerationCompleteEvent {
+                id: id_for_complete,
+                total,
+                error,
+            },
+        );
+    });
+
+    Ok(request_id)
+}
+
+/// Stops a `start_playlist_enumeration` request, which then completes with a
+/// cancelled error. Returns false if `id` isn't running.
+#[tauri::command]
+fn cancel_playlist_enumeration(id: String, state: State<'_, AppState>) -> bool {
+    match state.playlist_enumerations.lock().unwrap().remove(&id) {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartDownloadRequest {
+    /// Ignored by `start_batch_download`, which fills in each URL.
+    #[serde(default)]
+    pub url: String,
+    pub output_dir: String,
+    /// `"thumbnail"` for cover art only; otherwise audio when `audio_format`
+    /// is set and video if not.
+    pub mode: Option<String>,
+    // 비디오 옵션
+    pub video_quality: Option<String>,
+    pub video_container: Option<String>,
+    pub video_codec: Option<String>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    // 오디오 옵션
+    pub audio_format: Option<String>,
+    pub audio_lang: Option<String>,
+    pub audio_quality: Option<String>,
+    #[serde(default)]
+    pub keep_video: bool,
+    // 기존
+    pub embed_subs: bool,
+    pub sub_langs: Option<Vec<String>>,
+    #[serde(default)]
+    pub write_auto_subs: bool,
+    #[serde(default)]
+    pub write_subs_external: bool,
+    pub sub_format: Option<String>,
+    pub playlist_items: Option<Vec<usize>>,
+    pub playlist_order: Option<String>,
+    #[serde(default)]
+    pub organize_playlist: bool,
+    pub sponsorblock_remove: Option<Vec<String>>,
+    #[serde(default)]
+    pub use_netrc: bool,
+    pub netrc_path: Option<String>,
+    #[serde(default)]
+    pub no_merge: bool,
+    #[serde(default)]
+    pub write_manifest: bool,
+    pub mp4_faststart: Option<bool>,
+    #[serde(default)]
+    pub dual_audio_output: bool,
+    pub mirror_dirs: Option<Vec<String>>,
+    pub burn_subtitles: Option<String>,
+    pub retry_sleep: Option<String>,
+    pub fragment_retries: Option<String>,
+    pub match_filter: Option<String>,
+    pub min_duration: Option<u32>,
+    pub max_duration: Option<u32>,
+    #[serde(default)]
+    pub embed_thumbnail: bool,
+    pub thumbnail_index: Option<usize>,
+    #[serde(default)]
+    pub embed_source_url: bool,
+    #[serde(default)]
+    pub embed_metadata: bool,
+    pub postprocessor_args: Option<Vec<(String, String)>>,
+    pub channel_tab: Option<String>,
+    pub download_sections: Option<String>,
+    #[serde(default)]
+    pub live_from_start: bool,
+    #[serde(default)]
+    pub force_keyframes_at_cuts: bool,
+    pub max_retries: Option<u32>,
+    pub external_downloader: Option<String>,
+    pub output_template: Option<String>,
+    #[serde(default)]
+    pub restrict_filenames: bool,
+    pub trim_filenames: Option<usize>,
+    pub on_existing: Option<String>,
+    pub rate_limit: Option<String>,
+    pub cookies_from_browser: Option<String>,
+    pub format_id: Option<String>,
+    pub cookies_file: Option<PathBuf>,
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub geo_bypass: bool,
+    pub geo_bypass_country: Option<String>,
+    pub http_headers: Option<HashMap<String, String>>,
+    pub user_agent: Option<String>,
+}
+
+fn parse_audio_format(value: &str) -> AudioFormat {
+    match value {
+        "mp3" => AudioFormat::Mp3,
+        "m4a" => AudioFormat::M4a,
+        "aac" => AudioFormat::Aac,
+        "flac" => AudioFormat::Flac,
+        "wav" => AudioFormat::Wav,
+        "opus" => AudioFormat::Opus,
+        "vorbis" | "ogg" => AudioFormat::Vorbis,
+        _ => AudioFormat::Mp3, // 기본값
+    }
+}
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const PROGRESS_ACK_TIMEOUT: Duration = Duration::from_millis(250);
+// Backoff doubles from the base delay after each failed attempt, up to the cap
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Feeds one download's progress to the frontend through a latest-value channel.
+/// Until the frontend acknowledges an event (or `PROGRESS_ACK_TIMEOUT` passes),
+/// newer updates replace the pending one instead of queueing up. The last value
+/// sent before the sender is dropped is always emitted.
+fn spawn_progress_emitter(
+    app: AppHandle,
+    id: String,
+) -> (
+    watch::Sender<Option<DownloadProgress>>,
+    tokio::task::JoinHandle<()>,
+) {
+    let (tx, mut rx) = watch::channel(None);
+    let acks = app.state::<AppState>().progress_acks.clone();
+    let ack = Arc::new(Notify::new());
+    acks.lock().unwrap().insert(id.clone(), ack.clone());
+
+    let handle = tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let Some(progress) = rx.borrow_and_update().clone() else {
+                continue;
+            };
+            let _ = app.emit(
                 "download-progress",
                 DownloadProgressEvent {
-                    id: download_id_for_error,
-                    status: "error".to_string(),
-                    percentage: None,
-                    speed: None,
-                    eta: None,
-                    filename: Some(e.to_string()),
+                    id: id.clone(),
+                    progress,
                 },
             );
+            let _ = tokio::time::timeout(PROGRESS_ACK_TIMEOUT, ack.notified()).await;
+        }
+        // A resumed download may already have registered its own ack under this id
+        let mut acks = acks.lock().unwrap();
+        if acks.get(&id).is_some_and(|a| Arc::ptr_eq(a, &ack)) {
+            acks.remove(&id);
         }
     });
 
-    // Release the lock before returning
-    drop(downloader_guard);
-
-    Ok(download_id)
+    (tx, handle)
+}
+
+// Runs a download in the background once the queue allows it, reporting
+// progress under a fresh id which is returned immediately. Transient failures
+// are retried with exponential backoff, resuming from the leftover `.part` file
+// when it looks intact.
+fn spawn_download(
+    app: AppHandle,
+    downloader: Downloader,
+    queue: Arc<DownloadQueue>,
+    options: DownloadOptions,
+) -> String {
+    let download_id = Uuid::new_v4().to_string();
+    spawn_download_with_id(app, downloader, queue, download_id.clone(), options);
+    download_id
+}
+
+fn spawn_download_with_id(
+    app: AppHandle,
+    downloader: Downloader,
+    queue: Arc<DownloadQueue>,
+    download_id: String,
+    options: DownloadOptions,
+) {
+    let download_id_for_task = download_id.clone();
+    let cancel = queue.track(&download_id, &options);
+    let queue_for_task = queue.clone();
+
+    let task = tokio::spawn(async move {
+        let queue = queue_for_task;
+        let (progress_tx, emitter) =
+            spawn_progress_emitter(app.clone(), download_id_for_task.clone());
+        queue.add_task(&download_id_for_task, emitter.abort_handle());
+
+        if queue.is_paused() || !queue.has_free_slot() {
+            let _ = progress_tx.send(Some(DownloadProgress {
+                status: "queued".to_string(),
+                ..Default::default()
+            }));
+        }
+        // Held until the task ends, retries included
+        let slot = tokio::select! {
+            slot = queue.acquire_slot() => Some(slot),
+            _ = cancel.cancelled() => None,
+        };
+        let cancelled_while_queued = slot.is_none();
+        if !cancelled_while_queued {
+            queue.mark_active(&download_id_for_task);
+        }
+
+        // Last destination and expected size seen, used to vet the partial on retry
+        let last_file: Arc<std::sync::Mutex<(Option<String>, Option<u64>)>> =
+            Arc::new(std::sync::Mutex::new((None, None)));
+        let completed: Arc<std::sync::Mutex<Option<DownloadProgress>>> =
+            Arc::new(std::sync::Mutex::new(None));
+
+        let max_retries = options.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut attempt = 1;
+        let result = loop {
+            if cancelled_while_queued {
+                break Err(DownloaderError::Cancelled);
+            }
+
+            let tx_for_progress = progress_tx.clone();
+            let last_file_for_progress = last_file.clone();
+            let queue_for_progress = queue.clone();
+            let id_for_progress = download_id_for_task.clone();
+            let completed_for_progress = completed.clone();
+            let result = downloader
+                .download(&options, cancel.clone(), move |progress| {
+                    queue_for_progress.record_activity(&id_for_progress, progress.pid);
+                    if progress.status == "completed" {
+                        *completed_for_progress.lock().unwrap() = Some(progress.clone());
+                    }
+                    if let Ok(mut last) = last_file_for_progress.lock() {
+                        if progress.filename.is_some() {
+                            *last = (progress.filename.clone(), None);
+                        }
+                        if progress.total_bytes.is_some() {
+                            last.1 = progress.total_bytes;
+                        }
+                    }
+                    let _ = tx_for_progress.send(Some(progress));
+                })
+                .await;
+
+            match result {
+                // Re-running wouldn't change a local ffmpeg failure; see `retry_post_processing`
+                Err(DownloaderError::Cancelled | DownloaderError::PostProcessingFailed { .. }) => {
+                    break result
+                }
+                Err(ref e) if attempt <= max_retries && ytdlp::downloader::is_transient_error(e) => {
+                    let delay = RETRY_BASE_DELAY
+                        .saturating_mul(1 << (attempt - 1).min(16))
+                        .min(RETRY_MAX_DELAY);
+                    attempt += 1;
+
+                    let (destination, expected_total) = last_file.lock().unwrap().clone();
+                    let check = destination
+                        .map(|d| ytdlp::downloader::check_partial_file(&d, expected_total));
+                    let _ = progress_tx.send(Some(DownloadProgress {
+                        status: "retrying".to_string(),
+                        attempt: Some(attempt),
+                        resume_offset: check.map(|c| c.resume_offset),
+                        partial_discarded: check.map(|c| c.discarded),
+                        ..Default::default()
+                    }));
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = cancel.cancelled() => break Err(DownloaderError::Cancelled),
+                    }
+                }
+                result => break result,
+            }
+        };
+
+        let paused = queue.untrack(&download_id_for_task);
+
+        if result.is_ok() {
+            let progress = completed.lock().unwrap().take();
+            if let Some(progress) = progress {
+                let _ = ytdlp::history::append(history_entry(&options, &progress));
+                notify_completed(&app, &progress).await;
+            }
+        }
+
+        if let Err(e) = result {
+            let mut output_files = None;
+            let error_category = match e {
+                DownloaderError::Cancelled => None,
+                ref e => Some(e.category().to_string()),
+            };
+            let (status, filename) = match e {
+                DownloaderError::Cancelled if paused => ("paused", None),
+                DownloaderError::Cancelled => ("cancelled", None),
+                DownloaderError::PostProcessingFailed { ref files, .. } => {
+                    queue.remember_postprocessing_failure(&download_id_for_task, &options);
+                    output_files = Some(files.clone());
+                    ("postprocessing_failed", Some(e.to_string()))
+                }
+                e => ("error", Some(e.to_string())),
+            };
+            let _ = progress_tx.send(Some(DownloadProgress {
+                status: status.to_string(),
+                filename,
+                output_files,
+                error_category,
+                ..Default::default()
+            }));
+        }
+
+        drop(slot);
+
+        // Dropping the sender lets the emitter flush the final status and exit
+        drop(progress_tx);
+        let _ = emitter.await;
+    });
+    queue.add_task(&download_id, task.abort_handle());
+}
+
+/// Notifies the user of a finished download by its output file name, when
+/// notifications are enabled.
+async fn notify_completed(app: &AppHandle, progress: &DownloadProgress) {
+    let enabled = app.state::<AppState>().settings.lock().await.notifications_enabled;
+    if !enabled {
+        return;
+    }
+
+    let Some(name) = progress.filename.as_ref().and_then(|p| {
+        std::path::Path::new(p)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+    }) else {
+        return;
+    };
+    let _ = app
+        .notification()
+        .builder()
+        .title("Download complete")
+        .body(name)
+        .show();
+}
+
+fn history_entry(options: &DownloadOptions, progress: &DownloadProgress) -> HistoryEntry {
+    let output_path = progress.filename.clone();
+    HistoryEntry {
+        url: options.url.clone(),
+        // The file name only stands in when yt-dlp reported no title, e.g.
+        // for a file that was already downloaded or an aria2c transfer
+        title: progress.title.clone().or_else(|| {
+            output_path.as_ref().and_then(|p| {
+                std::path::Path::new(p)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+            })
+        }),
+        mode: match options.mode {
+            DownloadMode::Video { .. } => "video",
+            DownloadMode::Audio { .. } => "audio",
+            DownloadMode::Thumbnail => "thumbnail",
+        }
+        .to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        size: progress.total_bytes.or_else(|| {
+            output_path
+                .as_ref()
+                .and_then(|p| std::fs::metadata(p).ok())
+                .map(|m| m.len())
+        }),
+        output_path,
+    }
+}
+
+/// Re-runs a download whose post-processing failed. yt-dlp finds the already
+/// downloaded streams on disk and skips straight to post-processing.
+/// Returns the id of the new download.
+#[tauri::command]
+async fn retry_post_processing(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let options = state
+        .queue
+        .take_postprocessing_failure(&id)
+        .ok_or("No failed post-processing to retry for this download")?;
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    Ok(spawn_download(app, downloader, state.queue.clone(), options))
+}
+
+/// Tells videos, playlists and channels apart, listing the tabs a channel
+/// URL can be narrowed to.
+#[tauri::command]
+fn classify_url(url: String) -> ytdlp::downloader::UrlClassification {
+    ytdlp::downloader::classify_url(&url)
+}
+
+/// Stops a running or queued download. The download task emits the final
+/// `cancelled` event; `.part` files are kept so it can be resumed later.
+#[tauri::command]
+fn cancel_download(id: String, state: State<'_, AppState>) -> bool {
+    state.queue.cancel(&id)
+}
+
+/// Stops a download but keeps its `.part` file and options, emitting a final
+/// `paused` event. `resume_download` continues it under the same id.
+#[tauri::command]
+fn pause_download(id: String, state: State<'_, AppState>) -> bool {
+    state.queue.pause_download(&id)
+}
+
+#[tauri::command]
+async fn resume_download(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let options = state
+        .queue
+        .take_paused(&id)
+        .ok_or("This download isn't paused")?;
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    spawn_download_with_id(app, downloader, state.queue.clone(), id, options);
+    Ok(())
+}
+
+/// Kills a download's processes outright when cancelling doesn't stop it,
+/// e.g. yt-dlp stuck on a dead socket. Emits a final `error` event.
+#[tauri::command]
+fn force_kill_download(app: AppHandle, id: String, state: State<'_, AppState>) -> bool {
+    if !state.queue.force_kill(&id) {
+        return false;
+    }
+    // The emitter task was aborted before it could remove its ack
+    state.progress_acks.lock().unwrap().remove(&id);
+
+    let error = DownloaderError::DownloadFailed("Download was force-killed".to_string());
+    let _ = app.emit(
+        "download-progress",
+        DownloadProgressEvent {
+            id,
+            progress: DownloadProgress {
+                status: "error".to_string(),
+                filename: Some("Download was force-killed".to_string()),
+                error_category: Some(error.category().to_string()),
+                ..Default::default()
+            },
+        },
+    );
+    true
+}
+
+/// Called by the frontend once it has rendered a progress event, letting the
+/// next (latest) update for that download through.
+#[tauri::command]
+fn ack_download_progress(id: String, state: State<'_, AppState>) {
+    if let Some(ack) = state.progress_acks.lock().unwrap().get(&id) {
+        ack.notify_one();
+    }
+}
+
+/// Header map from the frontend as sorted pairs, so the arguments are stable.
+fn header_pairs(headers: Option<HashMap<String, String>>) -> Option<Vec<(String, String)>> {
+    headers.map(|headers| {
+        let mut pairs: Vec<(String, String)> = headers.into_iter().collect();
+        pairs.sort();
+        pairs
+    })
+}
+
+/// Turns a frontend request into `DownloadOptions`, filling in saved defaults.
+async fn download_options(request: StartDownloadRequest, state: &AppState) -> DownloadOptions {
+    let mode = if request.mode.as_deref() == Some("thumbnail") {
+        DownloadMode::Thumbnail
+    } else if let Some(audio_fmt) = request.audio_format {
+        // 오디오 모드
+        DownloadMode::Audio {
+            format: parse_audio_format(&audio_fmt),
+            audio_lang: request.audio_lang,
+            quality: request.audio_quality,
+            keep_video: request.keep_video,
+        }
+    } else {
+        // 비디오 모드
+        let quality = match request.video_quality.as_deref() {
+            Some("2160p") => VideoQuality::P2160,
+            Some("1440p") => VideoQuality::P1440,
+            Some("1080p") => VideoQuality::P1080,
+            Some("720p") => VideoQuality::P720,
+            Some("480p") => VideoQuality::P480,
+            Some("smart") => VideoQuality::Smart {
+                max_height: request.max_height.unwrap_or(1080),
+            },
+            _ => VideoQuality::Best, // 기본값
+        };
+        let container = match request.video_container.as_deref() {
+            Some("mkv") => VideoContainer::Mkv,
+            Some("webm") => VideoContainer::Webm,
+            Some("mov") => VideoContainer::Mov,
+            Some("avi") => VideoContainer::Avi,
+            Some("smart_mp4") => VideoContainer::SmartMp4,
+            _ => VideoContainer::Mp4, // 기본값
+        };
+        DownloadMode::Video {
+            quality,
+            container,
+            video_codec: request.video_codec,
+        }
+    };
+
+    // Fall back to the saved default languages when none were picked
+    let sub_langs = match request.sub_langs {
+        Some(langs) if !langs.is_empty() => Some(langs),
+        _ if request.embed_subs || request.write_subs_external => {
+            let defaults = state.settings.lock().await.default_subtitle_langs.clone();
+            (!defaults.is_empty()).then_some(defaults)
+        }
+        _ => None,
+    };
+
+    DownloadOptions {
+        url: request.url,
+        output_dir: request.output_dir,
+        mode,
+        embed_subs: request.embed_subs,
+        sub_langs,
+        write_auto_subs: request.write_auto_subs,
+        write_subs_external: request.write_subs_external,
+        sub_format: request.sub_format,
+        playlist_items: request.playlist_items,
+        playlist_order: request.playlist_order,
+        organize_playlist: request.organize_playlist,
+        sponsorblock_remove: request.sponsorblock_remove,
+        use_netrc: request.use_netrc,
+        netrc_path: request.netrc_path,
+        no_merge: request.no_merge,
+        write_manifest: request.write_manifest,
+        load_info_json: None,
+        mp4_faststart: request.mp4_faststart.unwrap_or(true),
+        min_height: request.min_height,
+        max_height: request.max_height,
+        dual_audio_output: request.dual_audio_output,
+        mirror_dirs: request.mirror_dirs,
+        burn_subtitles: request.burn_subtitles,
+        retry_sleep: request.retry_sleep,
+        fragment_retries: request.fragment_retries,
+        match_filter: request.match_filter,
+        min_duration: request.min_duration,
+        max_duration: request.max_duration,
+        embed_thumbnail: request.embed_thumbnail,
+        thumbnail_index: request.thumbnail_index,
+        embed_source_url: request.embed_source_url,
+        embed_metadata: request.embed_metadata,
+        postprocessor_args: request.postprocessor_args,
+        channel_tab: request.channel_tab,
+        download_sections: request.download_sections,
+        live_from_start: request.live_from_start,
+        force_keyframes_at_cuts: request.force_keyframes_at_cuts,
+        max_retries: request.max_retries,
+        external_downloader: request.external_downloader,
+        output_template: request.output_template,
+        restrict_filenames: request.restrict_filenames,
+        trim_filenames: request.trim_filenames,
+        on_existing: request.on_existing,
+        rate_limit: request.rate_limit,
+        cookies_from_browser: request.cookies_from_browser,
+        format_id: request.format_id,
+        cookies_file: request.cookies_file,
+        proxy: request.proxy,
+        geo_bypass: request.geo_bypass,
+        geo_bypass_country: request.geo_bypass_country,
+        http_headers: header_pairs(request.http_headers),
+        user_agent: request.user_agent,
+    }
+}
+
+#[tauri::command]
+async fn start_download(
+    app: AppHandle,
+    request: StartDownloadRequest,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+
+    // Release the lock before the download starts
+    drop(downloader_guard);
+
+    let options = download_options(request, &state).await;
+
+    Ok(spawn_download(app, downloader, state.queue.clone(), options))
+}
+
+/// Queues one download per URL, all with the same `options`. URLs that only
+/// differ by tracking parameters are downloaded once. Returns the download ids
+/// in the order of `urls`.
+#[tauri::command]
+async fn start_batch_download(
+    app: AppHandle,
+    urls: Vec<String>,
+    options: StartDownloadRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let template = download_options(options, &state).await;
+    let mut seen = HashSet::new();
+    let ids = urls
+        .into_iter()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty() && seen.insert(ytdlp::history::normalize_url(url)))
+        .map(|url| {
+            let options = DownloadOptions {
+                url,
+                ..template.clone()
+            };
+            spawn_download(app.clone(), downloader.clone(), state.queue.clone(), options)
+        })
+        .collect();
+
+    Ok(ids)
+}
+
+/// Predicts the files and formats `start_download` would produce for `request`.
+#[tauri::command]
+async fn simulate_download(
+    request: StartDownloadRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<SimulatedFile>, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let options = download_options(request, &state).await;
+    downloader
+        .simulate_download(&options)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Audio-only re-download of a URL from the history, reusing the info fetched
+/// earlier when it is still fresh.
+#[tauri::command]
+async fn extract_audio_from_url(
+    app: AppHandle,
+    url: String,
+    format: String,
+    output_dir: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    if !ytdlp::history::contains(&url).map_err(|e| e.to_string())? {
+        return Err("This URL isn't in the download history".to_string());
+    }
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let options = DownloadOptions {
+        load_info_json: downloader
+            .cached_info_json(&url)
+            .map(|p| p.to_string_lossy().to_string()),
+        url,
+        output_dir,
+        mode: DownloadMode::Audio {
+            format: parse_audio_format(&format),
+            audio_lang: None,
+            quality: None,
+            keep_video: false,
+        },
+        ..Default::default()
+    };
+
+    Ok(spawn_download(app, downloader, state.queue.clone(), options))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractorWarmedEvent {
+    pub url: String,
+    pub success: bool,
+}
+
+/// Primes yt-dlp in the background; an `extractor-warmed` event follows.
+/// Returns false without doing anything if it already ran this session.
+#[tauri::command]
+async fn warm_extractor(
+    app: AppHandle,
+    url: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    if state.extractor_warmed.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+
+    let warmed = state.extractor_warmed.clone();
+    tokio::spawn(async move {
+        let success = downloader.warm_extractor(&url).await.unwrap_or(false);
+        if !success {
+            // Let the next call try again
+            warmed.store(false, Ordering::SeqCst);
+        }
+        let _ = app.emit("extractor-warmed", ExtractorWarmedEvent { url, success });
+    });
+
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamProgressEvent {
+    pub path: String,
+    #[serde(flatten)]
+    pub progress: DownloadProgress,
+}
+
+/// Downloads for immediate playback and returns the file path as soon as it is
+/// playable. Progress of the remainder is sent as `stream-progress` events.
+/// Streaming a URL again stops its earlier stream, which writes the same file.
+#[tauri::command]
+async fn get_stream_to_file(
+    app: AppHandle,
+    url: String,
+    format: Option<String>,
+    network: Option<InfoNetworkOptions>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let extra_args = network.unwrap_or_default().into_args()?;
+
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let cancel = CancellationToken::new();
+    let streams = state.streams.clone();
+    if let Some(previous) = streams.lock().unwrap().insert(url.clone(), cancel.clone()) {
+        previous.cancel();
+    }
+
+    let streams_for_events = streams.clone();
+    let url_for_events = url.clone();
+    let cancel_for_events = cancel.clone();
+    // Error events carry the message in `filename`, so the path is remembered
+    let last_path = std::sync::Mutex::new(String::new());
+    let result = downloader
+        .stream_to_file(&url, format.as_deref(), &extra_args, cancel, move |progress| {
+            let path = {
+                let mut last_path = last_path.lock().unwrap();
+                if progress.status != "error" {
+                    if let Some(filename) = &progress.filename {
+                        *last_path = filename.clone();
+                    }
+                }
+                last_path.clone()
+            };
+            // A cancelled stream has already been replaced or removed
+            if matches!(progress.status.as_str(), "completed" | "error")
+                && !cancel_for_events.is_cancelled()
+            {
+                streams_for_events.lock().unwrap().remove(&url_for_events);
+            }
+            let _ = app.emit("stream-progress", StreamProgressEvent { path, progress });
+        })
+        .await;
+
+    match result {
+        Ok(path) => Ok(path.to_string_lossy().to_string()),
+        Err(DownloaderError::Cancelled) => Err(DownloaderError::Cancelled.to_string()),
+        Err(e) => {
+            streams.lock().unwrap().remove(&url);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Stops the stream of `url`, deleting its file. Returns false if none is running.
+#[tauri::command]
+fn cancel_stream(url: String, state: State<'_, AppState>) -> bool {
+    match state.streams.lock().unwrap().remove(&url) {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Completed downloads, oldest first.
+#[tauri::command]
+fn get_download_history() -> Result<Vec<HistoryEntry>, String> {
+    ytdlp::history::load().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn clear_download_history() -> Result<(), String> {
+    ytdlp::history::clear().map_err(|e| e.to_string())
+}
+
+/// Writes the download history to `path` as CSV and returns the number of entries.
+#[tauri::command]
+fn export_history_csv(path: String) -> Result<usize, String> {
+    ytdlp::history::export_csv(&PathBuf::from(path)).map_err(|e| e.to_string())
+}
+
+/// Pre-download check: earlier downloads of `url` whose files still exist, so
+/// the UI can offer "download anyway / skip" instead of overwriting them.
+#[tauri::command]
+fn check_existing_download(url: String) -> Result<Vec<HistoryEntry>, String> {
+    ytdlp::history::find_existing(&url).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn test_proxy(url: String) -> ProxyTestResult {
+    ytdlp::proxy::test_proxy(&url).await
+}
+
+#[tauri::command]
+fn encode_config(options: DownloadOptions) -> Result<String, String> {
+    ytdlp::share::encode_config(&options).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn decode_config(code: String) -> Result<DownloadOptions, String> {
+    ytdlp::share::decode_config(&code).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_ffmpeg_install_guidance() -> FfmpegInstallGuidance {
+    YtDlpManager::get_ffmpeg_install_guidance()
 }
 
 #[tauri::command]
@@ -264,22 +1480,490 @@ fn get_default_download_dir() -> String {
         .to_string()
 }
 
+#[tauri::command]
+async fn measure_disk_write_speed(dir: String) -> Result<f64, String> {
+    tokio::task::spawn_blocking(move || ytdlp::disk::measure_write_speed(&PathBuf::from(dir)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_disk_space(path: String) -> Result<DiskSpace, String> {
+    tokio::task::spawn_blocking(move || ytdlp::disk::disk_space(&PathBuf::from(path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_sponsorblock_categories() -> Vec<SponsorBlockCategory> {
+    ytdlp::sponsorblock::CATEGORIES.to_vec()
+}
+
+#[tauri::command]
+async fn find_duplicates(
+    app: AppHandle,
+    dir: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let cancel = state.duplicate_scan_cancel.clone();
+    cancel.store(false, Ordering::Relaxed);
+
+    // Hashing is CPU/IO heavy, keep it off the async runtime
+    tokio::task::spawn_blocking(move || {
+        ytdlp::duplicates::find_duplicates(&PathBuf::from(dir), &cancel, |progress: DuplicateScanProgress| {
+            let _ = app.emit("duplicate-scan-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn cancel_duplicate_scan(state: State<'_, AppState>) {
+    state.duplicate_scan_cancel.store(true, Ordering::Relaxed);
+}
+
+#[tauri::command]
+async fn batch_convert(
+    app: AppHandle,
+    dir: String,
+    target: ConvertTarget,
+    state: State<'_, AppState>,
+) -> Result<BatchConvertResult, String> {
+    let manager = YtDlpManager::new().map_err(|e| e.to_string())?;
+    let cancel = CancellationToken::new();
+    *state.batch_convert_cancel.lock().unwrap() = Some(cancel.clone());
+
+    let result = ytdlp::convert::batch_convert(
+        &manager,
+        &PathBuf::from(dir),
+        &target,
+        &state.queue,
+        &cancel,
+        |progress: BatchConvertProgress| {
+            let _ = app.emit("batch-convert-progress", progress);
+        },
+    )
+    .await;
+    state.batch_convert_cancel.lock().unwrap().take();
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn cancel_batch_convert(state: State<'_, AppState>) {
+    if let Some(cancel) = state.batch_convert_cancel.lock().unwrap().take() {
+        cancel.cancel();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerStateEvent {
+    pub on_battery: bool,
+    pub queue_paused: bool,
+}
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Saves unfinished downloads, cancels them and waits (bounded) for their
+// processes to exit so nothing is orphaned when the app closes.
+async fn shutdown_downloads(queue: &DownloadQueue) {
+    let _ = queue.persist();
+    if queue.cancel_all() == 0 {
+        return;
+    }
+
+    let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+        while queue.tracked_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+}
+
+#[tauri::command]
+async fn shutdown(state: State<'_, AppState>) -> Result<(), String> {
+    shutdown_downloads(&state.queue).await;
+    Ok(())
+}
+
+const POWER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Pauses the queue and any running downloads while unplugged when
+// `pause_on_battery` is enabled, resuming them once back on AC power.
+// Machines without a battery never report a state, so this stays idle there.
+async fn monitor_power(app: AppHandle) {
+    let mut paused_for_battery = false;
+    let mut paused_ids: Vec<String> = Vec::new();
+
+    loop {
+        tokio::time::sleep(POWER_POLL_INTERVAL).await;
+
+        let state = app.state::<AppState>();
+        let enabled = state.settings.lock().await.pause_on_battery;
+        let on_battery = if enabled {
+            tokio::task::spawn_blocking(ytdlp::power::is_on_battery)
+                .await
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+
+        match on_battery {
+            Some(true) if !paused_for_battery && !state.queue.is_paused() => {
+                state.queue.pause();
+                paused_ids = state.queue.state().active;
+                paused_ids.retain(|id| state.queue.pause_download(id));
+                paused_for_battery = true;
+            }
+            Some(false) | None if paused_for_battery => {
+                state.queue.resume();
+                let downloader = state.downloader.lock().await.as_ref().cloned();
+                if let Some(downloader) = downloader {
+                    for id in paused_ids.drain(..) {
+                        // Skips downloads the user has already resumed themselves
+                        if let Some(options) = state.queue.take_paused(&id) {
+                            spawn_download_with_id(
+                                app.clone(),
+                                downloader.clone(),
+                                state.queue.clone(),
+                                id,
+                                options,
+                            );
+                        }
+                    }
+                }
+                paused_for_battery = false;
+            }
+            _ => continue,
+        }
+
+        let _ = app.emit(
+            "power-state-changed",
+            PowerStateEvent {
+                on_battery: on_battery.unwrap_or(false),
+                queue_paused: state.queue.is_paused(),
+            },
+        );
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadStalledEvent {
+    pub id: String,
+    pub idle_secs: u64,
+}
+
+const STALL_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_STALL_TIMEOUT_SECS: u64 = 300;
+
+// Flags running downloads that have gone quiet for `stall_timeout_secs` so the
+// UI can offer `force_kill_download`.
+async fn monitor_stalls(app: AppHandle) {
+    loop {
+        tokio::time::sleep(STALL_POLL_INTERVAL).await;
+
+        let state = app.state::<AppState>();
+        let timeout = state
+            .settings
+            .lock()
+            .await
+            .stall_timeout_secs
+            .unwrap_or(DEFAULT_STALL_TIMEOUT_SECS);
+
+        for (id, idle) in state.queue.take_stalled(Duration::from_secs(timeout)) {
+            let _ = app.emit(
+                "download-stalled",
+                DownloadStalledEvent {
+                    id,
+                    idle_secs: idle.as_secs(),
+                },
+            );
+        }
+    }
+}
+
+#[tauri::command]
+fn pause_queue(state: State<'_, AppState>) {
+    state.queue.pause();
+}
+
+#[tauri::command]
+fn resume_queue(state: State<'_, AppState>) {
+    state.queue.resume();
+}
+
+#[tauri::command]
+async fn set_pause_on_battery(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let mut settings = state.settings.lock().await;
+    settings.pause_on_battery = enabled;
+    settings.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_default_subtitle_langs(
+    langs: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if let Some(invalid) = langs
+        .iter()
+        .find(|l| !ytdlp::settings::is_valid_subtitle_lang(l))
+    {
+        return Err(format!("Invalid subtitle language code: {}", invalid));
+    }
+
+    let mut settings = state.settings.lock().await;
+    settings.default_subtitle_langs = langs;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Changes how many downloads run at once. Running downloads are never
+/// stopped; a lower limit applies as they finish.
+#[tauri::command]
+async fn set_max_concurrent(n: usize, state: State<'_, AppState>) -> Result<(), String> {
+    if n == 0 {
+        return Err("At least one download must be allowed to run".to_string());
+    }
+    state.queue.set_max_concurrent(n);
+
+    let mut settings = state.settings.lock().await;
+    settings.max_concurrent_downloads = Some(n);
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Re-queues the downloads that were unfinished when the app last closed,
+/// returning their new ids.
+#[tauri::command]
+async fn restore_queue(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let downloader_guard = state.downloader.lock().await;
+    let downloader = downloader_guard
+        .as_ref()
+        .ok_or("Downloader not initialized. Please install yt-dlp first.")?
+        .clone();
+    drop(downloader_guard);
+
+    let pending = DownloadQueue::take_persisted().map_err(|e| e.to_string())?;
+    Ok(pending
+        .into_iter()
+        .map(|options| spawn_download(app.clone(), downloader.clone(), state.queue.clone(), options))
+        .collect())
+}
+
+/// Running, waiting and individually paused download ids.
+#[tauri::command]
+fn get_queue_state(state: State<'_, AppState>) -> QueueState {
+    state.queue.state()
+}
+
+/// Sets how long a download may go without progress before it's reported as
+/// stalled. `None` restores the default.
+#[tauri::command]
+async fn set_stall_timeout(secs: Option<u64>, state: State<'_, AppState>) -> Result<(), String> {
+    if secs == Some(0) {
+        return Err("Stall timeout must be at least one second".to_string());
+    }
+
+    let mut settings = state.settings.lock().await;
+    settings.stall_timeout_secs = secs;
+    settings.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn load_settings(state: State<'_, AppState>) -> Result<Settings, String> {
+    Ok(state.settings.lock().await.clone())
+}
+
+/// Replaces the saved settings. The yt-dlp version, release proxy and mirror
+/// are kept as they are, since changing them goes through their own commands.
+#[tauri::command]
+async fn save_settings(settings: Settings, state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(invalid) = settings
+        .default_subtitle_langs
+        .iter()
+        .find(|l| !ytdlp::settings::is_valid_subtitle_lang(l))
+    {
+        return Err(format!("Invalid subtitle language code: {}", invalid));
+    }
+    if let Some(rate) = &settings.rate_limit {
+        if !ytdlp::downloader::is_valid_rate_limit(rate) {
+            return Err(format!("Invalid rate limit: {}", rate));
+        }
+    }
+    if settings.max_concurrent_downloads == Some(0) {
+        return Err("At least one download must be allowed to run".to_string());
+    }
+    if settings.stall_timeout_secs == Some(0) {
+        return Err("Stall timeout must be at least one second".to_string());
+    }
+
+    let mut current = state.settings.lock().await;
+    let settings = Settings {
+        active_ytdlp_version: current.active_ytdlp_version.clone(),
+        release_proxy: current.release_proxy.clone(),
+        release_mirror_base: current.release_mirror_base.clone(),
+        ..settings
+    };
+    settings.save().map_err(|e| e.to_string())?;
+
+    state.queue.set_max_concurrent(
+        settings
+            .max_concurrent_downloads
+            .unwrap_or(ytdlp::queue::DEFAULT_MAX_CONCURRENT),
+    );
+    *current = settings;
+    Ok(())
+}
+
+fn new_updater(proxy: Option<&str>) -> Result<Updater, UpdaterError> {
+    match proxy {
+        Some(proxy) => Updater::with_proxy(proxy),
+        None => Updater::new(),
+    }
+}
+
+/// Sets (or clears, with `None`) the proxy used for yt-dlp release downloads.
+#[tauri::command]
+async fn set_release_proxy(
+    proxy: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let proxy = proxy.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(proxy) = &proxy {
+        if !ytdlp::proxy::is_valid_proxy_url(proxy) {
+            return Err(format!("Invalid proxy URL: {}", proxy));
+        }
+    }
+
+    let mut settings = state.settings.lock().await;
+    let mut updater = new_updater(proxy.as_deref()).map_err(|e| e.to_string())?;
+    updater.set_mirror_base(settings.release_mirror_base.clone());
+    *state.updater.lock().await = Some(updater);
+
+    settings.release_proxy = proxy;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Sets (or clears, with `None`) the mirror used for yt-dlp release downloads.
+#[tauri::command]
+async fn set_release_mirror_base(
+    base: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let base = base.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+    if let Some(base) = &base {
+        if !ytdlp::settings::is_valid_mirror_base(base) {
+            return Err(format!("Invalid mirror URL: {}", base));
+        }
+    }
+
+    if let Some(updater) = state.updater.lock().await.as_mut() {
+        updater.set_mirror_base(base.clone());
+    }
+
+    let mut settings = state.settings.lock().await;
+    settings.release_mirror_base = base;
+    settings.save().map_err(|e| e.to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState::default())
+        .setup(|app| {
+            tauri::async_runtime::spawn_blocking(|| {
+                let _ = ytdlp::downloader::clear_stream_files();
+            });
+            tauri::async_runtime::spawn(monitor_power(app.handle().clone()));
+            tauri::async_runtime::spawn(monitor_stalls(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_app_status,
+            load_settings,
+            save_settings,
             get_ytdlp_version,
+            verify_ytdlp,
+            fix_permissions,
+            ytdlp_supports_option,
             check_update,
             download_ytdlp,
+            cancel_ytdlp_download,
+            self_update_ytdlp,
+            download_ffmpeg,
+            list_installed_versions,
+            install_ytdlp_version,
+            switch_version,
+            remove_version,
             get_video_info,
+            get_extractor_timings,
+            start_playlist_enumeration,
+            cancel_playlist_enumeration,
+            get_playlist_details,
             start_download,
+            start_batch_download,
+            simulate_download,
+            ack_download_progress,
+            extract_audio_from_url,
+            capture_frame,
+            get_stream_to_file,
+            cancel_stream,
+            warm_extractor,
+            get_format_filesize,
+            list_formats,
+            fetch_thumbnail,
             get_default_download_dir,
+            get_ffmpeg_install_guidance,
+            test_proxy,
+            get_download_history,
+            clear_download_history,
+            export_history_csv,
+            check_existing_download,
+            measure_disk_write_speed,
+            get_disk_space,
+            encode_config,
+            decode_config,
+            get_sponsorblock_categories,
+            find_duplicates,
+            cancel_duplicate_scan,
+            batch_convert,
+            cancel_batch_convert,
+            pause_queue,
+            resume_queue,
+            set_pause_on_battery,
+            set_default_subtitle_langs,
+            set_release_mirror_base,
+            set_release_proxy,
+            set_stall_timeout,
+            set_max_concurrent,
+            get_queue_state,
+            restore_queue,
+            cancel_download,
+            pause_download,
+            resume_download,
+            classify_url,
+            force_kill_download,
+            retry_post_processing,
+            shutdown,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // `code` is only set for our own `exit` call below, which must go through
+            if let tauri::RunEvent::ExitRequested { code: None, api, .. } = event {
+                api.prevent_exit();
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    shutdown_downloads(&app.state::<AppState>().queue).await;
+                    app.exit(0);
+                });
+            }
+        });
 }
@@ -0,0 +1,271 @@
+use crate::ytdlp::downloader::{parse_timestamp, AudioFormat, VideoContainer};
+use crate::ytdlp::duplicates::is_media_file;
+use crate::ytdlp::manager::YtDlpManager;
+use crate::ytdlp::queue::DownloadQueue;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Converting files requires ffmpeg")]
+    FfmpegNotFound,
+    #[error("Conversion cancelled")]
+    Cancelled,
+    #[error("ffmpeg failed: {0}")]
+    FfmpegFailed(String),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ConvertTarget {
+    Video { container: VideoContainer },
+    Audio { format: AudioFormat },
+}
+
+const REMUX_ARGS: &[&str] = &["-c", "copy"];
+const X264_ARGS: &[&str] = &[
+    "-c:v", "libx264", "-crf", "20", "-preset", "medium", "-c:a", "aac",
+];
+const AVI_ARGS: &[&str] = &[
+    "-c:v", "libx264", "-crf", "20", "-preset", "medium", "-c:a", "libmp3lame", "-q:a", "2",
+];
+const VP9_ARGS: &[&str] = &[
+    "-c:v",
+    "libvpx-vp9",
+    "-crf",
+    "32",
+    "-b:v",
+    "0",
+    "-c:a",
+    "libopus",
+];
+
+impl ConvertTarget {
+    /// Output extension and ffmpeg codec arguments to try, in order. Video
+    /// targets remux first and only re-encode if the streams don't fit;
+    /// `SmartMp4` never re-encodes and falls back to an MKV remux instead.
+    fn attempts(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        match self {
+            ConvertTarget::Video { container } => match container {
+                VideoContainer::Mp4 => vec![("mp4", REMUX_ARGS), ("mp4", X264_ARGS)],
+                VideoContainer::Mkv => vec![("mkv", REMUX_ARGS), ("mkv", X264_ARGS)],
+                VideoContainer::Webm => vec![("webm", REMUX_ARGS), ("webm", VP9_ARGS)],
+                VideoContainer::Mov => vec![("mov", REMUX_ARGS), ("mov", X264_ARGS)],
+                VideoContainer::Avi => vec![("avi", REMUX_ARGS), ("avi", AVI_ARGS)],
+                VideoContainer::SmartMp4 => vec![("mp4", REMUX_ARGS), ("mkv", REMUX_ARGS)],
+            },
+            ConvertTarget::Audio { format } => vec![match format {
+                AudioFormat::Mp3 => ("mp3", &["-vn", "-c:a", "libmp3lame", "-q:a", "2"][..]),
+                AudioFormat::M4a => ("m4a", &["-vn", "-c:a", "aac", "-b:a", "192k"][..]),
+                AudioFormat::Aac => ("aac", &["-vn", "-c:a", "aac", "-b:a", "192k"][..]),
+                AudioFormat::Flac => ("flac", &["-vn", "-c:a", "flac"][..]),
+                AudioFormat::Wav => ("wav", &["-vn", "-c:a", "pcm_s16le"][..]),
+                AudioFormat::Opus => ("opus", &["-vn", "-c:a", "libopus", "-b:a", "160k"][..]),
+                AudioFormat::Vorbis => ("ogg", &["-vn", "-c:a", "libvorbis", "-q:a", "5"][..]),
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchConvertProgress {
+    pub index: usize,
+    pub total: usize,
+    pub file: String,
+    /// Progress of the current file.
+    pub percentage: Option<f64>,
+    pub overall_percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConvertFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchConvertResult {
+    pub converted: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<ConvertFailure>,
+}
+
+/// Converts every media file directly inside `dir` to `target`, writing the
+/// result next to the source. Video targets are remuxed when the streams fit
+/// the new container and re-encoded otherwise. Files already in the target
+/// format, or whose output already exists, are skipped.
+pub async fn batch_convert<F>(
+    manager: &YtDlpManager,
+    dir: &Path,
+    target: &ConvertTarget,
+    queue: &DownloadQueue,
+    cancel: &CancellationToken,
+    on_progress: F,
+) -> Result<BatchConvertResult, ConvertError>
+where
+    F: Fn(BatchConvertProgress),
+{
+    if !dir.is_dir() {
+        return Err(ConvertError::NotADirectory(
+            dir.to_string_lossy().to_string(),
+        ));
+    }
+    if !manager.is_ffmpeg_installed() {
+        return Err(ConvertError::FfmpegNotFound);
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_media_file(path))
+        .collect();
+    files.sort();
+
+    let attempts = target.attempts();
+    let ext = attempts[0].0;
+    let total = files.len();
+    let mut result = BatchConvertResult::default();
+
+    for (index, source) in files.into_iter().enumerate() {
+        let source_str = source.to_string_lossy().to_string();
+        let same_format = source
+            .extension()
+            .map(|e| e.to_string_lossy().eq_ignore_ascii_case(ext))
+            .unwrap_or(false);
+        if same_format || source.with_extension(ext).exists() {
+            result.skipped.push(source_str);
+            continue;
+        }
+
+        // Share the download queue's slots: nothing new starts while it's paused
+        // or full, so conversions don't compete with running downloads
+        let _slot = tokio::select! {
+            slot = queue.acquire_slot() => slot,
+            _ = cancel.cancelled() => return Err(ConvertError::Cancelled),
+        };
+
+        let report = |percentage: Option<f64>| {
+            on_progress(BatchConvertProgress {
+                index,
+                total,
+                file: source_str.clone(),
+                percentage,
+                overall_percentage: (index as f64 + percentage.unwrap_or(0.0) / 100.0)
+                    / total as f64
+                    * 100.0,
+            })
+        };
+        report(Some(0.0));
+
+        let mut outcome = Err(ConvertError::FfmpegFailed(String::new()));
+        for (attempt_ext, codec_args) in &attempts {
+            let output = source.with_extension(attempt_ext);
+            // A fallback must not clobber the source or an unrelated existing file
+            if output == source || output.exists() {
+                continue;
+            }
+
+            outcome = run_ffmpeg(manager, &source, &output, codec_args, cancel, &report)
+                .await
+                .map(|_| output.clone());
+            if outcome.is_err() {
+                let _ = std::fs::remove_file(&output);
+            }
+            if !matches!(outcome, Err(ConvertError::FfmpegFailed(_))) {
+                break;
+            }
+        }
+
+        match outcome {
+            Ok(output) => result.converted.push(output.to_string_lossy().to_string()),
+            Err(ConvertError::Cancelled) => return Err(ConvertError::Cancelled),
+            Err(e) => result.failed.push(ConvertFailure {
+                path: source_str,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(result)
+}
+
+async fn run_ffmpeg<F>(
+    manager: &YtDlpManager,
+    input: &Path,
+    output: &Path,
+    codec_args: &[&str],
+    cancel: &CancellationToken,
+    on_percentage: &F,
+) -> Result<(), ConvertError>
+where
+    F: Fn(Option<f64>),
+{
+    let mut cmd = Command::new(manager.get_ffmpeg_path());
+    cmd.args(["-y", "-nostats", "-progress", "pipe:2", "-i"])
+        .arg(input)
+        .args(codec_args)
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = cmd.spawn()?;
+    let mut lines = BufReader::new(child.stderr.take().unwrap()).lines();
+
+    let duration_regex = Regex::new(r"Duration:\s*(\d+:\d+:\d+(?:\.\d+)?)").unwrap();
+    let mut duration = None;
+    let mut tail = Vec::new();
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line?,
+            _ = cancel.cancelled() => {
+                let _ = child.kill().await;
+                return Err(ConvertError::Cancelled);
+            }
+        };
+        let Some(line) = line else { break };
+
+        if duration.is_none() {
+            if let Some(caps) = duration_regex.captures(&line) {
+                duration = parse_timestamp(&caps[1]);
+            }
+        }
+
+        if let Some(out_us) = line.strip_prefix("out_time_us=") {
+            let elapsed = out_us.trim().parse::<f64>().unwrap_or(0.0) / 1_000_000.0;
+            on_percentage(
+                duration
+                    .filter(|d| *d > 0.0)
+                    .map(|d| (elapsed / d * 100.0).clamp(0.0, 100.0)),
+            );
+        } else if !line.contains('=') {
+            tail.push(line);
+            if tail.len() > 20 {
+                tail.remove(0);
+            }
+        }
+    }
+
+    if !child.wait().await?.success() {
+        return Err(ConvertError::FfmpegFailed(tail.join("\n")));
+    }
+    Ok(())
+}
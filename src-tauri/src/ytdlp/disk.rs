@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+const WRITE_TEST_CHUNK: usize = 1024 * 1024;
+const WRITE_TEST_CHUNKS: usize = 8;
+
+/// Measures sequential write throughput of `dir` in MB/s using a temporary file.
+/// Blocking; run on a blocking thread.
+pub fn measure_write_speed(dir: &Path) -> std::io::Result<f64> {
+    let temp_path = dir.join(format!(".yt-dlp-gui-speedtest-{}.tmp", uuid::Uuid::new_v4()));
+    let result = write_test_file(&temp_path);
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+/// Writes `contents` to `path` through a temp file and a rename, creating the
+/// parent dir if needed, so a crash or a concurrent reader never sees a
+/// truncated file.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskSpace {
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Free and total space of the filesystem containing `path`. The output dir
+/// may not exist yet, so the nearest existing ancestor is queried instead.
+pub fn disk_space(path: &Path) -> std::io::Result<DiskSpace> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Path does not exist"))?;
+
+    Ok(DiskSpace {
+        available_bytes: fs2::available_space(existing)?,
+        total_bytes: fs2::total_space(existing)?,
+    })
+}
+
+fn write_test_file(path: &Path) -> std::io::Result<f64> {
+    let chunk = vec![0xA5u8; WRITE_TEST_CHUNK];
+    let start = Instant::now();
+
+    let mut file = std::fs::File::create(path)?;
+    for _ in 0..WRITE_TEST_CHUNKS {
+        file.write_all(&chunk)?;
+    }
+    // Make sure the data actually reached the disk, not just the page cache
+    file.sync_all()?;
+
+    let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    let megabytes = (WRITE_TEST_CHUNK * WRITE_TEST_CHUNKS) as f64 / (1024.0 * 1024.0);
+    Ok(megabytes / elapsed)
+}
@@ -1,10 +1,19 @@
 use crate::ytdlp::manager::YtDlpManager;
+use crate::ytdlp::manifest::DownloadManifest;
+use crate::ytdlp::proxy::is_valid_proxy_url;
+use crate::ytdlp::sponsorblock;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -12,6 +21,47 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+// Stream URLs inside a cached info.json expire after a few hours on most sites
+const INFO_CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+
+// Machine-readable progress lines: downloaded/total/estimated total/speed/ETA/title.
+// The title goes last since it may itself contain slashes
+const PROGRESS_PREFIX: &str = "__progress__ ";
+const PROGRESS_TEMPLATE: &str = "download:__progress__ %(progress.downloaded_bytes)s/%(progress.total_bytes)s/%(progress.total_bytes_estimate)s/%(progress.speed)s/%(progress.eta)s/%(info.is_live)s/%(info.title)s";
+
+// How long yt-dlp gets to exit after SIGTERM before it is killed
+const TERMINATE_GRACE: Duration = Duration::from_secs(3);
+
+// `--downloader-args` for aria2c: 16 connections per file, and a readout
+// every second since it otherwise prints one a minute when not on a terminal
+const ARIA2C_ARGS: &str = "-x16 -s16 --summary-interval=1";
+
+// Output template for `organize_playlist`; yt-dlp creates the subfolder
+const PLAYLIST_OUTPUT_TEMPLATE: &str = "%(playlist_title)s/%(playlist_index)s - %(title)s.%(ext)s";
+
+// Shortest `trim_filenames`; anything shorter leaves titles unrecognizable
+const MIN_TRIM_FILENAMES: usize = 11;
+
+// What `SmartMp4` asks yt-dlp for when the probe can't decide
+const SMART_CONTAINER_FALLBACK: &str = "mp4/mkv";
+
+// Stderr lines kept in the error when yt-dlp fails without an `ERROR:` line
+const STDERR_TAIL_LINES: usize = 5;
+
+// Full info fetches run at once by `get_playlist_details`; more than a few
+// gets rate-limited by YouTube
+const PLAYLIST_DETAILS_CONCURRENCY: usize = 4;
+
+// Info-fetch timings kept per extractor for `extractor_timings`
+const MAX_TIMING_SAMPLES: usize = 20;
+
+// Bytes on disk before a streaming download is considered playable
+const STREAM_BUFFER_BYTES: u64 = 2 * 1024 * 1024;
+const STREAM_DEFAULT_FORMAT: &str = "b[protocol^=http]/b";
+
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "vtt", "ass", "ssa"];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "mov"];
+
 #[derive(Error, Debug)]
 pub enum DownloaderError {
     #[error("yt-dlp binary not found. Please install yt-dlp first.")]
@@ -26,25 +76,560 @@ pub enum DownloaderError {
     JsonError(#[from] serde_json::Error),
     #[error("Manager error: {0}")]
     ManagerError(String),
+    #[error("Download cancelled")]
+    Cancelled,
+    #[error("This content is not available in your country{}. Try enabling geo-bypass or using a proxy.", format_allowed_countries(.allowed_countries))]
+    GeoBlocked {
+        allowed_countries: Option<String>,
+        message: String,
+    },
+    /// The media was downloaded but a postprocessor (usually ffmpeg) failed.
+    /// `files` are the downloaded, unprocessed files, which are kept on disk.
+    #[error("Download finished but post-processing failed: {message}")]
+    PostProcessingFailed { message: String, files: Vec<String> },
+    #[error("This video is unavailable: {0}")]
+    VideoUnavailable(String),
+    #[error("This video is private. Sign in with cookies from a browser that can see it.")]
+    PrivateVideo(String),
+    #[error("This video is age-restricted. Sign in with cookies from a browser to download it.")]
+    AgeRestricted(String),
+    #[error("Network error: {0}")]
+    NetworkError(String),
+}
+
+impl DownloaderError {
+    /// Stable category name the frontend can pick guidance by.
+    pub fn category(&self) -> &'static str {
+        match self {
+            DownloaderError::VideoUnavailable(_) => "video_unavailable",
+            DownloaderError::GeoBlocked { .. } => "geo_blocked",
+            DownloaderError::PrivateVideo(_) => "private_video",
+            DownloaderError::AgeRestricted(_) => "age_restricted",
+            DownloaderError::NetworkError(_) => "network_error",
+            DownloaderError::PostProcessingFailed { .. } => "postprocessing_failed",
+            DownloaderError::Cancelled => "cancelled",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Selector limited to `[min, max]` height that falls back to the best available
+/// streams when nothing satisfies the bounds.
+pub fn height_range_format(min_height: Option<u32>, max_height: Option<u32>) -> String {
+    let filter = height_filter(min_height, max_height);
+    format!("bv*{0}+ba/b{0}/bv*+ba/b", filter)
+}
+
+/// yt-dlp format filter matching `codec`, or None for an unknown codec.
+pub fn video_codec_filter(codec: &str) -> Option<&'static str> {
+    match codec.to_ascii_lowercase().as_str() {
+        "av1" => Some("[vcodec^=av01]"),
+        "h264" | "avc" => Some("[vcodec^=avc1]"),
+        "h265" | "hevc" => Some("[vcodec~='^(hev|hvc)1']"),
+        "vp9" => Some("[vcodec~='^vp0?9']"),
+        _ => None,
+    }
+}
+
+/// Puts a codec-filtered copy of the selector's first video choice in front,
+/// so the plain selector still applies when that codec isn't offered.
+fn prefer_video_codec(selector: &str, codec_filter: &str) -> String {
+    let first = selector.split(['/', ',']).next().unwrap_or(selector);
+    let (video, rest) = first.split_once('+').unwrap_or((first, ""));
+    let preferred = if rest.is_empty() {
+        format!("{}{}", video, codec_filter)
+    } else {
+        format!("{}{}+{}", video, codec_filter, rest)
+    };
+    format!("{}/{}", preferred, selector)
+}
+
+fn height_filter(min_height: Option<u32>, max_height: Option<u32>) -> String {
+    let mut filter = String::new();
+    if let Some(min) = min_height {
+        filter.push_str(&format!("[height>={}]", min));
+    }
+    if let Some(max) = max_height {
+        filter.push_str(&format!("[height<={}]", max));
+    }
+    filter
+}
+
+// Whether any video format in an info.json satisfies the height bounds.
+// `None` when the formats can't be inspected.
+fn info_has_height_in_range(
+    info_path: &std::path::Path,
+    min_height: Option<u32>,
+    max_height: Option<u32>,
+) -> Option<bool> {
+    let content = std::fs::read_to_string(info_path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let formats = json["formats"].as_array()?;
+
+    Some(formats.iter().filter_map(|f| f["height"].as_u64()).any(|height| {
+        min_height.is_none_or(|min| height >= min as u64)
+            && max_height.is_none_or(|max| height <= max as u64)
+    }))
+}
+
+/// Combines the user's match filter with the duration bounds into one expression.
+pub fn build_match_filter(
+    match_filter: Option<&str>,
+    min_duration: Option<u32>,
+    max_duration: Option<u32>,
+) -> Option<String> {
+    let mut clauses: Vec<String> = match_filter
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(|f| vec![f.to_string()])
+        .unwrap_or_default();
+    if let Some(min) = min_duration {
+        clauses.push(format!("duration >=? {}", min));
+    }
+    if let Some(max) = max_duration {
+        clauses.push(format!("duration <=? {}", max));
+    }
+
+    (!clauses.is_empty()).then(|| clauses.join(" & "))
+}
+
+/// Whether any requested subtitle language is only available as an auto-caption.
+/// Without info this assumes so, unless `all` was requested: auto-captions for
+/// every language would pull in dozens of machine translations.
+pub fn needs_auto_captions(info: Option<&serde_json::Value>, langs: &[String]) -> bool {
+    let Some(info) = info else {
+        return !langs.iter().any(|l| l == "all");
+    };
+
+    langs.iter().filter(|l| l.as_str() != "all").any(|lang| {
+        info["subtitles"].get(lang.as_str()).is_none()
+            && info["automatic_captions"].get(lang.as_str()).is_some()
+    })
+}
+
+// Postprocessor names accepted by `--postprocessor-args`, lowercased
+const POSTPROCESSOR_NAMES: &[&str] = &[
+    "default",
+    "merger",
+    "modifychapters",
+    "splitchapters",
+    "extractaudio",
+    "videoremuxer",
+    "videoconvertor",
+    "metadata",
+    "embedsubtitle",
+    "embedthumbnail",
+    "subtitlesconvertor",
+    "thumbnailsconvertor",
+    "fixupstretched",
+    "fixupm4a",
+    "fixupm3u8",
+    "fixuptimestamp",
+    "fixupduration",
+];
+
+/// Validates a `--postprocessor-args` key: a postprocessor, an executable
+/// (`ffmpeg`, `ffprobe`, optionally `_i`/`_o` with an index), or `PP+EXE`.
+pub fn is_valid_postprocessor_name(name: &str) -> bool {
+    let exe_regex = Regex::new(r"^(ffmpeg|ffprobe)(_[io]\d*)?$").unwrap();
+    let name = name.to_lowercase();
+    let is_pp = |n: &str| POSTPROCESSOR_NAMES.contains(&n);
+    let is_exe = |n: &str| exe_regex.is_match(n);
+
+    match name.split_once('+') {
+        Some((pp, exe)) => is_pp(pp) && is_exe(exe),
+        None => is_pp(&name) || is_exe(&name),
+    }
+}
+
+const PRIVATE_VIDEO_PATTERNS: &[&str] = &["private video", "this video is private"];
+
+const AGE_RESTRICTED_PATTERNS: &[&str] = &[
+    "sign in to confirm your age",
+    "age-restricted",
+    "age restricted",
+    "inappropriate for some users",
+];
+
+const UNAVAILABLE_PATTERNS: &[&str] = &[
+    "video unavailable",
+    "has been removed",
+    "this video is not available",
+    "members-only",
+    "copyright",
+    "does not exist",
+    "http error 404",
+];
+
+// The underlying cause only: yt-dlp's own wrappers ("Got error:", "unable to
+// download video data:") also front permanent failures like HTTP 403
+const NETWORK_ERROR_PATTERNS: &[&str] = &[
+    "connection reset",
+    "connection refused",
+    "connection aborted",
+    "timed out",
+    "temporary failure",
+    "network is unreachable",
+    "remote end closed",
+    "incompleteread",
+    "http error 5",
+];
+
+/// Whether retrying might help: network hiccups and server-side errors, but
+/// not unavailable or private videos.
+pub fn is_transient_error(error: &DownloaderError) -> bool {
+    match error {
+        DownloaderError::NetworkError(_) => true,
+        // Not spawn failures or an unwritable output dir, which fail again
+        DownloaderError::IoError(e) => matches!(
+            e.kind(),
+            std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::UnexpectedEof
+        ),
+        _ => false,
+    }
+}
+
+/// Sorts a failed yt-dlp run into an error category by its stderr. None when
+/// nothing recognizable was printed; callers fall back to a generic error.
+pub fn classify_stderr(stderr: &str) -> Option<DownloaderError> {
+    if let Some(geo_error) = detect_geo_block(stderr) {
+        return Some(geo_error);
+    }
+
+    let errors: Vec<&str> = stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("ERROR:"))
+        .collect();
+    let message = errors
+        .last()
+        .copied()
+        .unwrap_or_else(|| stderr.lines().last().unwrap_or(""))
+        .trim()
+        .to_string();
+    // Only the errors, so a non-fatal warning (e.g. a missing thumbnail's 404)
+    // doesn't decide the category
+    let lower = if errors.is_empty() {
+        stderr.to_lowercase()
+    } else {
+        errors.join("\n").to_lowercase()
+    };
+    let matches_any = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+
+    if matches_any(PRIVATE_VIDEO_PATTERNS) {
+        Some(DownloaderError::PrivateVideo(message))
+    } else if matches_any(AGE_RESTRICTED_PATTERNS) {
+        Some(DownloaderError::AgeRestricted(message))
+    } else if matches_any(UNAVAILABLE_PATTERNS) {
+        Some(DownloaderError::VideoUnavailable(message))
+    } else if matches_any(NETWORK_ERROR_PATTERNS) {
+        Some(DownloaderError::NetworkError(message))
+    } else {
+        None
+    }
+}
+
+/// Returns the postprocessor's error if yt-dlp failed after downloading.
+pub fn detect_postprocessing_failure(stderr: &str) -> Option<String> {
+    stderr.lines().rev().find_map(|line| {
+        let (_, message) = line.split_once("ERROR: Postprocessing:")?;
+        Some(message.trim().to_string())
+    })
+}
+
+fn format_allowed_countries(countries: &Option<String>) -> String {
+    countries
+        .as_ref()
+        .map(|c| format!(" (only available in {})", c))
+        .unwrap_or_default()
+}
+
+/// Last few non-empty stderr lines, for failures without an `ERROR:` line.
+fn stderr_tail(stderr: &str) -> Option<String> {
+    let lines: Vec<&str> = stderr.lines().filter(|l| !l.trim().is_empty()).collect();
+    let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+    (!tail.is_empty()).then_some(tail)
+}
+
+/// Error for a failed yt-dlp run: its category if recognizable, else the last
+/// `ERROR:` line or the stderr tail, else `fallback`.
+fn failure_from_stderr(stderr: &str, fallback: &str) -> DownloaderError {
+    classify_stderr(stderr).unwrap_or_else(|| {
+        let message = stderr
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix("ERROR:"))
+            .map(|m| m.trim().to_string())
+            .or_else(|| stderr_tail(stderr))
+            .unwrap_or_else(|| fallback.to_string());
+        DownloaderError::DownloadFailed(message)
+    })
+}
+
+/// Where `stream_to_file` writes, separate from the user's downloads.
+fn stream_dir() -> PathBuf {
+    std::env::temp_dir().join("yt-dlp-gui").join("stream")
+}
+
+/// Deletes files left by earlier `stream_to_file` calls. Call before any
+/// stream starts, e.g. at startup.
+pub fn clear_stream_files() -> std::io::Result<()> {
+    match std::fs::remove_dir_all(stream_dir()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Recognizes yt-dlp's geo-restriction errors in stderr output.
+pub fn detect_geo_block(stderr: &str) -> Option<DownloaderError> {
+    let lower = stderr.to_lowercase();
+    let is_geo_blocked = [
+        "not available in your country",
+        "blocked in your country",
+        "not available from your location",
+        "geo restricted",
+        "geo-restricted",
+        "geo restriction",
+    ]
+    .iter()
+    .any(|pattern| lower.contains(pattern));
+
+    if !is_geo_blocked {
+        return None;
+    }
+
+    let message = stderr
+        .lines()
+        .rev()
+        .find(|line| line.contains("ERROR"))
+        .unwrap_or_else(|| stderr.lines().last().unwrap_or(""))
+        .trim()
+        .to_string();
+
+    let countries_regex = Regex::new(r"only available in ([A-Za-z][A-Za-z ,]*[A-Za-z])").unwrap();
+    let allowed_countries = countries_regex
+        .captures(stderr)
+        .map(|caps| caps[1].to_string());
+
+    Some(DownloaderError::GeoBlocked {
+        allowed_countries,
+        message,
+    })
+}
+
+pub const COOKIE_BROWSERS: &[&str] = &[
+    "brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi", "whale",
+];
+
+/// Validates the cookie sources and returns the matching yt-dlp arguments.
+pub fn cookie_args(
+    from_browser: Option<&str>,
+    file: Option<&std::path::Path>,
+) -> Result<Vec<String>, DownloaderError> {
+    let mut args = Vec::new();
+
+    if let Some(spec) = from_browser {
+        // BROWSER[+KEYRING][:PROFILE[::CONTAINER]]
+        let browser = spec.split([':', '+']).next().unwrap_or("");
+        if !COOKIE_BROWSERS.contains(&browser.to_ascii_lowercase().as_str()) {
+            return Err(DownloaderError::ExecutionError(format!(
+                "Unsupported browser for cookies: {}",
+                browser
+            )));
+        }
+        args.push("--cookies-from-browser".to_string());
+        args.push(spec.to_string());
+    }
+
+    if let Some(path) = file {
+        if !path.is_file() {
+            return Err(DownloaderError::ExecutionError(format!(
+                "Cookies file not found: {}",
+                path.display()
+            )));
+        }
+        args.push("--cookies".to_string());
+        args.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(args)
+}
+
+/// Validates `proxy` and returns the matching yt-dlp arguments.
+pub fn proxy_args(proxy: Option<&str>) -> Result<Vec<String>, DownloaderError> {
+    match proxy.map(str::trim) {
+        Some(url) if !is_valid_proxy_url(url) => Err(DownloaderError::ExecutionError(format!(
+            "Invalid proxy URL: {} (expected http://, https:// or socks5://)",
+            url
+        ))),
+        Some(url) => Ok(vec!["--proxy".to_string(), url.to_string()]),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Validates the geo-bypass options and returns the matching yt-dlp arguments.
+/// A country implies bypassing, so `geo_bypass` is only needed without one.
+pub fn geo_bypass_args(
+    geo_bypass: bool,
+    country: Option<&str>,
+) -> Result<Vec<String>, DownloaderError> {
+    match country.map(str::trim) {
+        Some(code) if code.len() != 2 || !code.chars().all(|c| c.is_ascii_uppercase()) => {
+            Err(DownloaderError::ExecutionError(format!(
+                "Invalid geo-bypass country: {} (expected a two-letter code like US)",
+                code
+            )))
+        }
+        Some(code) => Ok(vec!["--geo-bypass-country".to_string(), code.to_string()]),
+        None if geo_bypass => Ok(vec!["--geo-bypass".to_string()]),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Validates custom request headers and returns the matching yt-dlp arguments.
+pub fn header_args(
+    headers: Option<&[(String, String)]>,
+    user_agent: Option<&str>,
+) -> Result<Vec<String>, DownloaderError> {
+    let mut args = Vec::new();
+    for (key, value) in headers.unwrap_or_default() {
+        let key = key.trim();
+        if key.is_empty() || key.contains([':', '\r', '\n']) || value.contains(['\r', '\n']) {
+            return Err(DownloaderError::ExecutionError(format!(
+                "Invalid HTTP header: {}",
+                key
+            )));
+        }
+        args.push("--add-header".to_string());
+        args.push(format!("{}:{}", key, value.trim()));
+    }
+
+    if let Some(agent) = user_agent.map(str::trim).filter(|a| !a.is_empty()) {
+        if agent.contains(['\r', '\n']) {
+            return Err(DownloaderError::ExecutionError(
+                "The user agent must be a single line".to_string(),
+            ));
+        }
+        args.push("--user-agent".to_string());
+        args.push(agent.to_string());
+    }
+    Ok(args)
+}
+
+pub const CHANNEL_TABS: &[&str] = &["videos", "shorts", "streams", "playlists"];
+
+fn channel_url_regex() -> Regex {
+    Regex::new(
+        r"^(https?://(?:www\.|m\.)?youtube\.com/(?:@[^/?#]+|channel/[^/?#]+|c/[^/?#]+|user/[^/?#]+))(?:/([^/?#]+))?/?(?:[?#].*)?$",
+    )
+    .unwrap()
+}
+
+/// Rewrites a YouTube channel URL to point at `tab`, replacing any tab it
+/// already has. Returns None if `url` isn't a channel URL.
+pub fn channel_tab_url(url: &str, tab: &str) -> Option<String> {
+    let caps = channel_url_regex().captures(url.trim())?;
+    Some(format!("{}/{}", &caps[1], tab))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlKind {
+    Video,
+    Playlist,
+    Channel,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlClassification {
+    pub kind: UrlKind,
+    /// Tab the channel URL already points at, e.g. `shorts`.
+    pub channel_tab: Option<String>,
+    /// Tabs that can be picked with `channel_tab`; empty unless `kind` is channel.
+    pub channel_tabs: Vec<String>,
+}
+
+/// Classifies a URL from its shape alone, without a network request.
+pub fn classify_url(url: &str) -> UrlClassification {
+    let url = url.trim();
+    if let Some(caps) = channel_url_regex().captures(url) {
+        return UrlClassification {
+            kind: UrlKind::Channel,
+            channel_tab: caps.get(2).map(|m| m.as_str().to_string()),
+            channel_tabs: CHANNEL_TABS.iter().map(|t| t.to_string()).collect(),
+        };
+    }
+
+    let has_param = |name: &str| {
+        url.split(['?', '&'])
+            .skip(1)
+            .any(|pair| pair.starts_with(&format!("{}=", name)))
+    };
+    let kind = if has_param("list") && !has_param("v") {
+        UrlKind::Playlist
+    } else if has_param("v") || url.contains("youtu.be/") || url.contains("/shorts/") {
+        UrlKind::Video
+    } else {
+        UrlKind::Other
+    };
+
+    UrlClassification {
+        kind,
+        channel_tab: None,
+        channel_tabs: Vec::new(),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VideoQuality {
     Best,
+    #[serde(rename = "2160p")]
+    P2160,
+    #[serde(rename = "1440p")]
+    P1440,
+    #[serde(rename = "1080p")]
+    P1080,
     #[serde(rename = "720p")]
     P720,
     #[serde(rename = "480p")]
     P480,
+    /// Best streams up to `max_height`, falling back to a muxed format and
+    /// finally to anything available so the selector never comes up empty.
+    Smart { max_height: u32 },
 }
 
 impl VideoQuality {
-    pub fn to_format_string(&self) -> &'static str {
+    pub fn to_format_string(&self) -> String {
         match self {
-            VideoQuality::Best => "bv*+ba/b",
-            VideoQuality::P720 => "bv*[height<=720]+ba/b",
-            VideoQuality::P480 => "bv*[height<=480]+ba/b",
+            VideoQuality::Best => "bv*+ba/b".to_string(),
+            VideoQuality::P2160 => "bv*[height<=2160]+ba/b".to_string(),
+            VideoQuality::P1440 => "bv*[height<=1440]+ba/b".to_string(),
+            VideoQuality::P1080 => "bv*[height<=1080]+ba/b".to_string(),
+            VideoQuality::P720 => "bv*[height<=720]+ba/b".to_string(),
+            VideoQuality::P480 => "bv*[height<=480]+ba/b".to_string(),
+            VideoQuality::Smart { max_height } => format!(
+                "bv*[height<={0}]+ba/b[height<={0}]/bv*+ba/b",
+                max_height
+            ),
         }
     }
+
+    /// Selector that downloads the video and audio streams as two separate files.
+    pub fn to_separate_format_string(&self) -> String {
+        let video = match self {
+            VideoQuality::Best => "bv*".to_string(),
+            VideoQuality::P2160 => "bv*[height<=2160]".to_string(),
+            VideoQuality::P1440 => "bv*[height<=1440]".to_string(),
+            VideoQuality::P1080 => "bv*[height<=1080]".to_string(),
+            VideoQuality::P720 => "bv*[height<=720]".to_string(),
+            VideoQuality::P480 => "bv*[height<=480]".to_string(),
+            VideoQuality::Smart { max_height } => format!("bv*[height<={}]", max_height),
+        };
+        format!("{},ba", video)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +638,13 @@ pub enum VideoContainer {
     Mp4,
     Mkv,
     Webm,
+    /// QuickTime, for editing software. Merging into it needs ffmpeg.
+    Mov,
+    /// Not every codec fits in AVI; ffmpeg re-encodes what doesn't.
+    Avi,
+    /// MP4 when the selected streams fit without re-encoding, MKV otherwise.
+    #[serde(rename = "smart_mp4")]
+    SmartMp4,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +655,9 @@ pub enum AudioFormat {
     Aac,
     Flac,
     Wav,
+    Opus,
+    /// Ogg Vorbis; files end in `.ogg`.
+    Vorbis,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,10 +665,32 @@ pub enum DownloadMode {
     Video {
         quality: VideoQuality,
         container: VideoContainer,
+        /// Preferred video codec (`av1`, `h264`, `h265` or `vp9`); other codecs
+        /// are used when the preferred one isn't available.
+        video_codec: Option<String>,
     },
     Audio {
         format: AudioFormat,
+        /// Preferred audio track language; falls back to the best audio when missing.
+        audio_lang: Option<String>,
+        /// `--audio-quality`: VBR level `0` (best) to `9`, or a bitrate like `192K`.
+        quality: Option<String>,
+        /// Keep the downloaded file the audio was extracted from.
+        #[serde(default)]
+        keep_video: bool,
     },
+    /// Only the cover art, saved as JPEG.
+    Thumbnail,
+}
+
+impl Default for DownloadMode {
+    fn default() -> Self {
+        DownloadMode::Video {
+            quality: VideoQuality::Best,
+            container: VideoContainer::Mp4,
+            video_codec: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,7 +699,194 @@ pub struct DownloadOptions {
     pub output_dir: String,
     pub mode: DownloadMode,
     pub embed_subs: bool,
+    /// Subtitle languages to fetch; all of them when empty.
+    pub sub_langs: Option<Vec<String>>,
+    /// Also fetch auto-generated captions.
+    #[serde(default)]
+    pub write_auto_subs: bool,
+    /// Keep subtitles as `.srt` files next to the video. Independent of
+    /// `embed_subs`; either, both or neither may be set.
+    #[serde(default)]
+    pub write_subs_external: bool,
+    /// Preferred subtitle format, e.g. `srt`, `vtt` or `srt/best`.
+    pub sub_format: Option<String>,
     pub playlist_items: Option<Vec<usize>>,
+    /// `reverse` or `random`. Ignored when `playlist_items` are given.
+    pub playlist_order: Option<String>,
+    /// Save playlist downloads into a folder named after the playlist, with
+    /// numbered files. Replaces `output_template` for playlist URLs.
+    #[serde(default)]
+    pub organize_playlist: bool,
+    pub sponsorblock_remove: Option<Vec<String>>,
+    #[serde(default)]
+    pub use_netrc: bool,
+    pub netrc_path: Option<String>,
+    /// Keep the video-only and audio-only streams as separate files instead of
+    /// merging them. Incompatible with the `embed_*` options, which need a merged file.
+    #[serde(default)]
+    pub no_merge: bool,
+    /// Write a `.manifest.json` recording these options next to the output.
+    #[serde(default)]
+    pub write_manifest: bool,
+    /// Previously fetched info.json to download from instead of re-extracting `url`.
+    pub load_info_json: Option<String>,
+    /// Move the MP4 index to the front for streaming/web playback. Only applies to
+    /// merged MP4 output and costs an extra ffmpeg pass over the file.
+    #[serde(default = "default_true")]
+    pub mp4_faststart: bool,
+    /// Height bounds; when either is set they replace the quality preset's selector.
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    /// In audio mode, keep the extracted file and also write an MP3 copy next to it.
+    /// Needs ffmpeg and roughly doubles the disk space used per track.
+    #[serde(default)]
+    pub dual_audio_output: bool,
+    /// Extra directories the finished files are copied to, e.g. a NAS share.
+    /// An unreachable target is reported in `mirror_results` without failing the download.
+    pub mirror_dirs: Option<Vec<String>>,
+    /// Subtitle language to hardcode into a re-encoded `.burned` copy of the video.
+    /// This is a full re-encode with ffmpeg: slow, and slightly lossy.
+    pub burn_subtitles: Option<String>,
+    /// `--retry-sleep` expression, e.g. `5`, `linear=1::2` or `fragment:exp=1:20`.
+    pub retry_sleep: Option<String>,
+    /// `--fragment-retries`: a count or `infinite`.
+    pub fragment_retries: Option<String>,
+    /// Raw yt-dlp `--match-filter` expression.
+    pub match_filter: Option<String>,
+    /// Duration bounds in seconds, ANDed into the match filter. Items outside
+    /// them are skipped, not failed; items with no known duration are kept.
+    pub min_duration: Option<u32>,
+    pub max_duration: Option<u32>,
+    /// Embed the thumbnail as cover art. Needs ffmpeg.
+    #[serde(default)]
+    pub embed_thumbnail: bool,
+    /// Index into `VideoInfo::thumbnails` of the thumbnail to embed; yt-dlp's
+    /// best thumbnail is used when unset.
+    pub thumbnail_index: Option<usize>,
+    /// Record the source page URL in the file's `comment` metadata tag. Turns on
+    /// `--embed-metadata`, so it needs ffmpeg.
+    #[serde(default)]
+    pub embed_source_url: bool,
+    /// Write title, artist, upload date and chapter markers into the file. Needs ffmpeg.
+    #[serde(default)]
+    pub embed_metadata: bool,
+    /// Extra `--postprocessor-args` as (postprocessor or executable, arguments)
+    /// pairs, e.g. `("Merger+ffmpeg", "-ac 2")`.
+    pub postprocessor_args: Option<Vec<(String, String)>>,
+    /// Only download this tab of a YouTube channel URL (one of `CHANNEL_TABS`).
+    pub channel_tab: Option<String>,
+    /// Only download this time range, as `*HH:MM:SS-HH:MM:SS`. Needs ffmpeg.
+    pub download_sections: Option<String>,
+    /// Re-encode around cut points so cuts land exactly where asked instead of
+    /// on the nearest keyframe. Slower, and only matters when something is cut
+    /// out (SponsorBlock segment removal or `download_sections`).
+    #[serde(default)]
+    pub force_keyframes_at_cuts: bool,
+    /// Times a download is retried after a transient failure (default 3).
+    pub max_retries: Option<u32>,
+    /// External downloader for yt-dlp to hand the transfer to. Only `aria2c`
+    /// is supported; it must be on PATH.
+    pub external_downloader: Option<String>,
+    /// yt-dlp output template relative to `output_dir`, e.g.
+    /// `%(upload_date)s - %(title)s.%(ext)s`. Defaults to `%(title)s.%(ext)s`.
+    pub output_template: Option<String>,
+    /// Keep filenames to ASCII without spaces or `&`, avoiding reserved
+    /// characters on Windows.
+    #[serde(default)]
+    pub restrict_filenames: bool,
+    /// Cut filenames (without extension) to this many characters, for titles
+    /// that would exceed Windows' path length limit.
+    pub trim_filenames: Option<usize>,
+    /// What to do when the output file already exists: `skip`, `overwrite`,
+    /// or `rename` (yt-dlp's default).
+    pub on_existing: Option<String>,
+    /// Maximum download speed in bytes per second, e.g. `500K` or `2M`.
+    pub rate_limit: Option<String>,
+    /// Browser to read cookies from (one of `COOKIE_BROWSERS`), optionally with
+    /// a profile as in `firefox:default-release`.
+    pub cookies_from_browser: Option<String>,
+    /// A `format_id` from `list_formats` (or a selector like `137+140`) to use
+    /// instead of the one derived from `mode`.
+    pub format_id: Option<String>,
+    /// Netscape-format cookies file.
+    pub cookies_file: Option<PathBuf>,
+    /// `http://`, `https://` or `socks5://` proxy for all of yt-dlp's requests.
+    pub proxy: Option<String>,
+    /// Extra request headers, e.g. `("Referer", "https://example.com/")`.
+    pub http_headers: Option<Vec<(String, String)>>,
+    pub user_agent: Option<String>,
+    /// Record a live stream from its beginning instead of from now.
+    #[serde(default)]
+    pub live_from_start: bool,
+    /// Fake an IP from the content's region to get past geo-restrictions.
+    #[serde(default)]
+    pub geo_bypass: bool,
+    /// Two-letter ISO country code to appear to be from, e.g. `US`.
+    pub geo_bypass_country: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// Mirrors the serde defaults, so `mp4_faststart` starts out on
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            output_dir: String::new(),
+            mode: DownloadMode::default(),
+            embed_subs: false,
+            sub_langs: None,
+            write_auto_subs: false,
+            write_subs_external: false,
+            sub_format: None,
+            playlist_items: None,
+            playlist_order: None,
+            organize_playlist: false,
+            sponsorblock_remove: None,
+            use_netrc: false,
+            netrc_path: None,
+            no_merge: false,
+            write_manifest: false,
+            load_info_json: None,
+            mp4_faststart: default_true(),
+            min_height: None,
+            max_height: None,
+            dual_audio_output: false,
+            mirror_dirs: None,
+            burn_subtitles: None,
+            retry_sleep: None,
+            fragment_retries: None,
+            match_filter: None,
+            min_duration: None,
+            max_duration: None,
+            embed_thumbnail: false,
+            thumbnail_index: None,
+            embed_source_url: false,
+            embed_metadata: false,
+            postprocessor_args: None,
+            channel_tab: None,
+            download_sections: None,
+            force_keyframes_at_cuts: false,
+            max_retries: None,
+            external_downloader: None,
+            output_template: None,
+            restrict_filenames: false,
+            trim_filenames: None,
+            on_existing: None,
+            rate_limit: None,
+            cookies_from_browser: None,
+            format_id: None,
+            cookies_file: None,
+            proxy: None,
+            http_headers: None,
+            user_agent: None,
+            live_from_start: false,
+            geo_bypass: false,
+            geo_bypass_country: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,6 +900,53 @@ pub struct VideoInfo {
     pub is_playlist: bool,
     pub playlist_count: Option<usize>,
     pub entries: Option<Vec<PlaylistEntry>>,
+    pub audio_languages: Option<Vec<String>>,
+    /// Available thumbnails, in the order `thumbnail_index` refers to.
+    pub thumbnails: Vec<ThumbnailInfo>,
+    /// yt-dlp extractor that handled the URL, e.g. `Youtube`.
+    #[serde(default)]
+    pub extractor: Option<String>,
+    /// How long the info fetch took, in milliseconds.
+    #[serde(default)]
+    pub fetch_ms: u64,
+    // Single videos only
+    #[serde(default)]
+    pub view_count: Option<u64>,
+    #[serde(default)]
+    pub like_count: Option<u64>,
+    /// `YYYYMMDD`, as yt-dlp reports it.
+    #[serde(default)]
+    pub upload_date: Option<String>,
+    #[serde(default)]
+    pub webpage_url: Option<String>,
+    /// Size of the best format, exact or estimated.
+    #[serde(default)]
+    pub filesize_approx: Option<u64>,
+    /// A live stream; downloading it records until the stream ends.
+    #[serde(default)]
+    pub is_live: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractorTiming {
+    pub extractor: String,
+    pub samples: usize,
+    pub average_ms: u64,
+    pub last_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailInfo {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+fn parse_thumbnails(json: &serde_json::Value) -> Vec<serde_json::Value> {
+    json["thumbnails"]
+        .as_array()
+        .map(|arr| arr.iter().filter(|t| t["url"].is_string()).cloned().collect())
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,9 +955,168 @@ pub struct PlaylistEntry {
     pub title: String,
     pub duration: Option<f64>,
     pub thumbnail: Option<String>,
+    #[serde(default)]
+    pub uploader: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn parse_audio_languages(json: &serde_json::Value) -> Vec<String> {
+    let mut languages: Vec<String> = Vec::new();
+    if let Some(formats) = json["formats"].as_array() {
+        for format in formats {
+            if format["acodec"].as_str() == Some("none") {
+                continue;
+            }
+            if let Some(lang) = format["language"].as_str() {
+                if !languages.iter().any(|l| l == lang) {
+                    languages.push(lang.to_string());
+                }
+            }
+        }
+    }
+    languages
+}
+
+impl PlaylistEntry {
+    fn from_flat_json(entry: &serde_json::Value) -> Self {
+        Self {
+            id: entry["id"].as_str().unwrap_or("").to_string(),
+            title: entry["title"].as_str().unwrap_or("Unknown").to_string(),
+            duration: entry["duration"].as_f64(),
+            thumbnail: entry["thumbnail"].as_str().map(|s| s.to_string()),
+            uploader: entry["uploader"]
+                .as_str()
+                .or_else(|| entry["channel"].as_str())
+                .map(|s| s.to_string()),
+        }
+    }
+}
+
+/// Maps `get_video_info`'s `--dump-json --flat-playlist` output lines, of
+/// which there is at least one, to a `VideoInfo`.
+fn parse_video_info(
+    lines: &[&str],
+    extractor: Option<String>,
+    fetch_ms: u64,
+) -> Result<VideoInfo, DownloaderError> {
+    // Flat playlist entries carry their playlist's fields, so a playlist
+    // with a single entry is still recognized
+    let first: serde_json::Value = serde_json::from_str(lines[0])?;
+    let in_playlist = first["playlist_id"].is_string() || first["playlist_count"].is_u64();
+    if lines.len() > 1 || in_playlist {
+        let mut entries = Vec::new();
+        for line in lines {
+            if let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) {
+                entries.push(PlaylistEntry::from_flat_json(&entry));
+            }
+        }
+
+        return Ok(VideoInfo {
+            id: first["playlist_id"].as_str().unwrap_or("playlist").to_string(),
+            title: first["playlist_title"].as_str().unwrap_or("Playlist").to_string(),
+            duration: None,
+            thumbnail: None,
+            description: None,
+            uploader: first["playlist_uploader"].as_str().map(|s| s.to_string()),
+            is_playlist: true,
+            playlist_count: Some(
+                first["playlist_count"].as_u64().map_or(entries.len(), |c| c as usize),
+            ),
+            entries: Some(entries),
+            audio_languages: None,
+            thumbnails: Vec::new(),
+            extractor,
+            fetch_ms,
+            view_count: None,
+            like_count: None,
+            upload_date: None,
+            webpage_url: None,
+            filesize_approx: None,
+            is_live: false,
+        });
+    }
+
+    // Single video
+    let json = first;
+
+    // Check if the single entry is a playlist reference
+    if json.get("_type").and_then(|t| t.as_str()) == Some("playlist") {
+        let entries: Vec<PlaylistEntry> = json
+            .get("entries")
+            .and_then(|e| e.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|entry| {
+                        Some(PlaylistEntry {
+                            id: entry["id"].as_str()?.to_string(),
+                            title: entry["title"]
+                                .as_str()
+                                .unwrap_or("Unknown")
+                                .to_string(),
+                            duration: entry["duration"].as_f64(),
+                            thumbnail: entry["thumbnail"].as_str().map(|s| s.to_string()),
+                            uploader: entry["uploader"].as_str().map(|s| s.to_string()),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        return Ok(VideoInfo {
+            id: json["id"].as_str().unwrap_or("playlist").to_string(),
+            title: json["title"].as_str().unwrap_or("Playlist").to_string(),
+            duration: None,
+            thumbnail: json["thumbnail"].as_str().map(|s| s.to_string()),
+            description: None,
+            uploader: json["uploader"].as_str().map(|s| s.to_string()),
+            is_playlist: true,
+            playlist_count: Some(entries.len()),
+            entries: Some(entries),
+            audio_languages: None,
+            thumbnails: Vec::new(),
+            extractor,
+            fetch_ms,
+            view_count: None,
+            like_count: None,
+            upload_date: None,
+            webpage_url: None,
+            filesize_approx: None,
+            is_live: false,
+        });
+    }
+
+    Ok(VideoInfo {
+        id: json["id"].as_str().unwrap_or("").to_string(),
+        title: json["title"].as_str().unwrap_or("Unknown").to_string(),
+        duration: json["duration"].as_f64(),
+        thumbnail: json["thumbnail"].as_str().map(|s| s.to_string()),
+        description: json["description"].as_str().map(|s| s.to_string()),
+        uploader: json["uploader"].as_str().map(|s| s.to_string()),
+        is_playlist: false,
+        playlist_count: None,
+        entries: None,
+        audio_languages: Some(parse_audio_languages(&json)),
+        thumbnails: parse_thumbnails(&json)
+            .iter()
+            .map(|t| ThumbnailInfo {
+                url: t["url"].as_str().unwrap_or("").to_string(),
+                width: t["width"].as_u64().map(|w| w as u32),
+                height: t["height"].as_u64().map(|h| h as u32),
+            })
+            .collect(),
+        extractor,
+        fetch_ms,
+        view_count: json["view_count"].as_u64(),
+        like_count: json["like_count"].as_u64(),
+        upload_date: json["upload_date"].as_str().map(|s| s.to_string()),
+        webpage_url: json["webpage_url"].as_str().map(|s| s.to_string()),
+        filesize_approx: json["filesize"]
+            .as_u64()
+            .or_else(|| json["filesize_approx"].as_u64()),
+        is_live: json["is_live"].as_bool().unwrap_or(false),
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DownloadProgress {
     pub status: String,
     pub percentage: Option<f64>,
@@ -115,33 +1125,221 @@ pub struct DownloadProgress {
     pub filename: Option<String>,
     pub total_bytes: Option<u64>,
     pub downloaded_bytes: Option<u64>,
+    /// Every file written by the download, reported on completion.
+    pub output_files: Option<Vec<String>>,
+    pub warning: Option<String>,
+    // Retry reporting
+    pub attempt: Option<u32>,
+    pub resume_offset: Option<u64>,
+    pub partial_discarded: Option<bool>,
+    pub mirror_results: Option<Vec<MirrorResult>>,
+    /// `DownloaderError::category` of a failed download.
+    pub error_category: Option<String>,
+    /// Length recorded so far of a live stream, in seconds.
+    pub recorded_secs: Option<f64>,
+    /// Title of the item being downloaded, as yt-dlp reports it.
+    pub title: Option<String>,
+    // Completion summary
+    pub average_speed_bps: Option<u64>,
+    pub elapsed_secs: Option<f64>,
+    /// Container of the finished file, e.g. what `SmartMp4` settled on.
+    pub container: Option<String>,
+    /// Process id of the spawned yt-dlp, for the stall watchdog. Not sent to the UI.
+    #[serde(skip)]
+    pub pid: Option<u32>,
+}
+
+/// Copy outcome for one `mirror_dirs` target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorResult {
+    pub dir: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// Outcome of checking a leftover `.part` file before a retry.
+#[derive(Debug, Clone, Copy)]
+pub struct PartialCheck {
+    pub resume_offset: u64,
+    pub discarded: bool,
+}
+
+/// Verifies the `.part` file for `destination` is plausible to resume from:
+/// non-empty and no larger than the expected total. Implausible partials are deleted.
+pub fn check_partial_file(destination: &str, expected_total: Option<u64>) -> PartialCheck {
+    let partial = PathBuf::from(format!("{}.part", destination));
+    let Ok(metadata) = std::fs::metadata(&partial) else {
+        return PartialCheck {
+            resume_offset: 0,
+            discarded: false,
+        };
+    };
+
+    let size = metadata.len();
+    let corrupt = size == 0 || expected_total.is_some_and(|total| size > total);
+    if corrupt && std::fs::remove_file(&partial).is_ok() {
+        return PartialCheck {
+            resume_offset: 0,
+            discarded: true,
+        };
+    }
+
+    PartialCheck {
+        resume_offset: if corrupt { 0 } else { size },
+        discarded: false,
+    }
+}
+
+/// Size of a chosen format; `Unknown` when the site doesn't report one.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum FormatFilesize {
+    Exact { bytes: u64 },
+    Approximate { bytes: u64 },
+    Unknown,
+}
+
+/// A file `simulate_download` predicts, with the format yt-dlp would pick.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedFile {
+    pub filename: String,
+    pub format: String,
+}
+
+/// Arguments from `Downloader::build_args`.
+struct DownloadArgs {
+    args: Vec<String>,
+    /// Cookie and proxy arguments, for follow-up info fetches.
+    network_args: Vec<String>,
+    /// Temporary info JSON written for `thumbnail_index`, removed when dropped.
+    thumbnail_info: Option<TempFile>,
+    /// Flags left out because the installed yt-dlp doesn't support them.
+    dropped_flags: Vec<String>,
+}
+
+/// Deletes the file when dropped, so early returns and cancels clean up too.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Inputs to `build_download_args` that take a probe or an info fetch.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedInputs {
+    /// Container `SmartMp4` resolved to; `mp4/mkv` lets yt-dlp pick when unset.
+    pub smart_container: Option<&'static str>,
+    /// Fetch auto-generated captions too, because a requested language only
+    /// exists as one or `write_auto_subs` is set.
+    pub write_auto_subs: bool,
+    /// Info JSON to load instead of fetching the URL.
+    pub info_json_path: Option<String>,
+}
+
+/// One entry of yt-dlp's `formats` list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatInfo {
+    pub format_id: String,
+    pub ext: Option<String>,
+    pub resolution: Option<String>,
+    pub fps: Option<f64>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    /// Exact size, or yt-dlp's estimate when the site doesn't report one.
+    pub filesize: Option<u64>,
+    /// Total bitrate in KBit/s.
+    pub tbr: Option<f64>,
+    pub format_note: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct Downloader {
     manager: YtDlpManager,
+    info_cache: Arc<Mutex<HashMap<String, (PathBuf, Instant)>>>,
+    fetch_timings: Arc<Mutex<HashMap<String, VecDeque<u64>>>>,
 }
 
 impl Downloader {
     pub fn new() -> Result<Self, DownloaderError> {
         let manager =
             YtDlpManager::new().map_err(|e| DownloaderError::ManagerError(e.to_string()))?;
-        Ok(Self { manager })
+        Ok(Self {
+            manager,
+            info_cache: Arc::new(Mutex::new(HashMap::new())),
+            fetch_timings: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Path of a still-fresh info.json saved by `get_video_info` for this URL.
+    pub fn cached_info_json(&self, url: &str) -> Option<PathBuf> {
+        let cache = self.info_cache.lock().ok()?;
+        let (path, fetched_at) = cache.get(url)?;
+        (fetched_at.elapsed() < INFO_CACHE_TTL && path.exists()).then(|| path.clone())
+    }
+
+    fn cache_info_json(&self, url: &str, id: &str, json: &str) {
+        let dir = std::env::temp_dir().join("yt-dlp-gui").join("info");
+        if std::fs::create_dir_all(&dir).is_err() {
+            return;
+        }
+
+        let safe_id: String = id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let path = dir.join(format!("{}.info.json", safe_id));
+        if std::fs::write(&path, json).is_ok() {
+            if let Ok(mut cache) = self.info_cache.lock() {
+                cache.insert(url.to_string(), (path, Instant::now()));
+            }
+        }
+    }
+
+    fn record_fetch_timing(&self, extractor: &str, ms: u64) {
+        let mut timings = self.fetch_timings.lock().unwrap();
+        let samples = timings.entry(extractor.to_string()).or_default();
+        if samples.len() == MAX_TIMING_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(ms);
+    }
+
+    /// Recent info-fetch timings aggregated per extractor, slowest first.
+    pub fn extractor_timings(&self) -> Vec<ExtractorTiming> {
+        let timings = self.fetch_timings.lock().unwrap();
+        let mut result: Vec<ExtractorTiming> = timings
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(extractor, samples)| ExtractorTiming {
+                extractor: extractor.clone(),
+                samples: samples.len(),
+                average_ms: samples.iter().sum::<u64>() / samples.len() as u64,
+                last_ms: *samples.back().unwrap(),
+            })
+            .collect();
+        result.sort_by_key(|t| std::cmp::Reverse(t.average_ms));
+        result
     }
 
-    pub async fn get_video_info(&self, url: &str) -> Result<VideoInfo, DownloaderError> {
+    /// `extra_args` are network arguments from `cookie_args` and `proxy_args`,
+    /// for private or age-gated videos and proxied networks.
+    pub async fn get_video_info(
+        &self,
+        url: &str,
+        extra_args: &[String],
+    ) -> Result<VideoInfo, DownloaderError> {
         if !self.manager.is_ytdlp_installed() {
             return Err(DownloaderError::BinaryNotFound);
         }
 
+        let started = Instant::now();
         let mut cmd = Command::new(self.manager.get_ytdlp_path());
-        cmd.args([
-            "--dump-json",
-            "--flat-playlist",
-            "--no-warnings",
-            "--no-download",
-            url,
-        ]);
+        cmd.args(["--dump-json", "--flat-playlist", "--no-warnings", "--no-download"])
+            .args(extra_args)
+            .arg(url);
 
         #[cfg(target_os = "windows")]
         cmd.creation_flags(CREATE_NO_WINDOW);
@@ -149,9 +1347,8 @@ impl Downloader {
         let output = cmd.output().await?;
 
         if !output.status.success() {
-            return Err(DownloaderError::ExecutionError(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -163,150 +1360,1944 @@ impl Downloader {
             ));
         }
 
-        // Check if it's a playlist
-        if lines.len() > 1 {
-            // Multiple entries = playlist
-            let mut entries = Vec::new();
-            for line in &lines {
-                if let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) {
-                    entries.push(PlaylistEntry {
-                        id: entry["id"].as_str().unwrap_or("").to_string(),
-                        title: entry["title"].as_str().unwrap_or("Unknown").to_string(),
-                        duration: entry["duration"].as_f64(),
-                        thumbnail: entry["thumbnail"].as_str().map(|s| s.to_string()),
-                    });
-                }
-            }
-
-            return Ok(VideoInfo {
-                id: "playlist".to_string(),
-                title: "Playlist".to_string(),
-                duration: None,
-                thumbnail: None,
-                description: None,
-                uploader: None,
-                is_playlist: true,
-                playlist_count: Some(entries.len()),
-                entries: Some(entries),
+        // Flat playlist entries only carry the extractor of the entry itself
+        let extractor = serde_json::from_str::<serde_json::Value>(lines[0])
+            .ok()
+            .and_then(|json| {
+                json["extractor_key"]
+                    .as_str()
+                    .or_else(|| json["ie_key"].as_str())
+                    .map(|s| s.to_string())
             });
-        }
+        let fetch_ms = started.elapsed().as_millis() as u64;
+        self.record_fetch_timing(extractor.as_deref().unwrap_or("Unknown"), fetch_ms);
 
-        // Single video
-        let json: serde_json::Value = serde_json::from_str(lines[0])?;
-
-        // Check if the single entry is a playlist reference
-        if json.get("_type").and_then(|t| t.as_str()) == Some("playlist") {
-            let entries: Vec<PlaylistEntry> = json
-                .get("entries")
-                .and_then(|e| e.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|entry| {
-                            Some(PlaylistEntry {
-                                id: entry["id"].as_str()?.to_string(),
-                                title: entry["title"]
-                                    .as_str()
-                                    .unwrap_or("Unknown")
-                                    .to_string(),
-                                duration: entry["duration"].as_f64(),
-                                thumbnail: entry["thumbnail"].as_str().map(|s| s.to_string()),
-                            })
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            return Ok(VideoInfo {
-                id: json["id"].as_str().unwrap_or("playlist").to_string(),
-                title: json["title"].as_str().unwrap_or("Playlist").to_string(),
-                duration: None,
-                thumbnail: json["thumbnail"].as_str().map(|s| s.to_string()),
-                description: None,
-                uploader: json["uploader"].as_str().map(|s| s.to_string()),
-                is_playlist: true,
-                playlist_count: Some(entries.len()),
-                entries: Some(entries),
-            });
+        let info = parse_video_info(&lines, extractor, fetch_ms)?;
+        if !info.is_playlist && !info.id.is_empty() {
+            self.cache_info_json(url, &info.id, lines[0]);
         }
-
-        Ok(VideoInfo {
-            id: json["id"].as_str().unwrap_or("").to_string(),
-            title: json["title"].as_str().unwrap_or("Unknown").to_string(),
-            duration: json["duration"].as_f64(),
-            thumbnail: json["thumbnail"].as_str().map(|s| s.to_string()),
-            description: json["description"].as_str().map(|s| s.to_string()),
-            uploader: json["uploader"].as_str().map(|s| s.to_string()),
-            is_playlist: false,
-            playlist_count: None,
-            entries: None,
-        })
+        Ok(info)
     }
 
-    pub async fn download<F>(
+    /// Full (non-flat) info JSON for a single video, reusing the cached copy when fresh.
+    /// `extra_args` are network arguments, as for `get_video_info`.
+    pub async fn fetch_full_info(
         &self,
-        options: &DownloadOptions,
-        on_progress: F,
-    ) -> Result<String, DownloaderError>
-    where
-        F: Fn(DownloadProgress) + Send + 'static,
-    {
+        url: &str,
+        extra_args: &[String],
+    ) -> Result<serde_json::Value, DownloaderError> {
+        if let Some(path) = self.cached_info_json(url) {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                if let Ok(json) = serde_json::from_str(&content) {
+                    return Ok(json);
+                }
+            }
+        }
+
         if !self.manager.is_ytdlp_installed() {
             return Err(DownloaderError::BinaryNotFound);
         }
 
-        // Ensure output directory exists
-        let output_path = std::path::Path::new(&options.output_dir);
-        if !output_path.exists() {
-            std::fs::create_dir_all(output_path)?;
-        }
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args(["-J", "--no-playlist", "--no-warnings"])
+            .args(extra_args)
+            .arg(url);
 
-        // Build output template with proper path separator
-        let output_template = output_path
-            .join("%(title)s.%(ext)s")
-            .to_string_lossy()
-            .to_string();
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
 
-        let mut args = vec![
-            "--progress".to_string(),
+        let output = cmd.output().await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(stdout.trim())?;
+        if let Some(id) = json["id"].as_str() {
+            self.cache_info_json(url, id, stdout.trim());
+        }
+
+        Ok(json)
+    }
+
+    /// Picks the container for `SmartMp4`: `mp4` if the streams `selector` picks
+    /// are H.264/AV1 video with AAC audio, `mkv` if not. When the probe can't
+    /// tell (it failed, or the URL has several entries) yt-dlp gets `mp4/mkv`
+    /// and chooses per item by extension.
+    async fn probe_smart_container(
+        &self,
+        options: &DownloadOptions,
+        selector: &str,
+        network_args: &[String],
+    ) -> &'static str {
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args([
+            "--simulate",
+            "--no-warnings",
+            "--no-playlist",
+            "-f",
+            selector,
+            "--print",
+            "%(vcodec)s|%(acodec)s",
+        ])
+        .args(network_args);
+        match options
+            .load_info_json
+            .clone()
+            .map(PathBuf::from)
+            .or_else(|| self.cached_info_json(&options.url))
+        {
+            Some(path) => cmd.arg("--load-info-json").arg(path),
+            None => cmd.arg(&options.url),
+        };
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let Ok(output) = cmd.output().await else {
+            return SMART_CONTAINER_FALLBACK;
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().filter(|l| !l.trim().is_empty()).collect();
+        if !output.status.success() || lines.len() != 1 {
+            return SMART_CONTAINER_FALLBACK;
+        }
+
+        let (vcodec, acodec) = lines[0].split_once('|').unwrap_or((lines[0], "none"));
+        let video_ok = ["avc1", "avc3", "h264", "av01"]
+            .iter()
+            .any(|c| vcodec.starts_with(c));
+        let audio_ok = acodec == "none" || acodec.starts_with("mp4a") || acodec == "aac";
+        if video_ok && audio_ok {
+            "mp4"
+        } else {
+            "mkv"
+        }
+    }
+
+    /// Info JSON for a download: the supplied `load_info_json` file, or a fetch.
+    async fn load_info(&self, options: &DownloadOptions) -> Result<serde_json::Value, DownloaderError> {
+        match &options.load_info_json {
+            Some(path) => Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+            None => self.fetch_full_info(&options.url, &connection_args(options)?).await,
+        }
+    }
+
+    /// Writes a copy of the info JSON keeping only thumbnail `index`.
+    async fn write_thumbnail_info(
+        &self,
+        options: &DownloadOptions,
+        index: usize,
+    ) -> Result<TempFile, DownloaderError> {
+        let mut info = self.load_info(options).await?;
+        let thumbnails = parse_thumbnails(&info);
+        let chosen = thumbnails.get(index).cloned().ok_or_else(|| {
+            DownloaderError::ExecutionError(format!(
+                "Thumbnail index {} is out of range ({} available)",
+                index,
+                thumbnails.len()
+            ))
+        })?;
+
+        info["thumbnail"] = chosen["url"].clone();
+        info["thumbnails"] = serde_json::Value::Array(vec![chosen]);
+
+        let dir = std::env::temp_dir().join("yt-dlp-gui").join("info");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.thumb{}.info.json", uuid::Uuid::new_v4(), index));
+        let file = TempFile(path);
+        std::fs::write(&file.0, serde_json::to_string(&info)?)?;
+        Ok(file)
+    }
+
+    /// Size of `format_id`, summing the parts of a merged selection like `137+140`.
+    pub async fn get_format_filesize(
+        &self,
+        url: &str,
+        format_id: &str,
+        extra_args: &[String],
+    ) -> Result<FormatFilesize, DownloaderError> {
+        let json = self.fetch_full_info(url, extra_args).await?;
+        let formats = json["formats"].as_array().cloned().unwrap_or_default();
+
+        let mut total = 0;
+        let mut approximate = false;
+        for part in format_id.split('+') {
+            let format = formats
+                .iter()
+                .find(|f| f["format_id"].as_str() == Some(part))
+                .ok_or_else(|| DownloaderError::ExecutionError(format!("Unknown format: {}", part)))?;
+
+            if let Some(size) = format["filesize"].as_u64() {
+                total += size;
+            } else if let Some(size) = format["filesize_approx"].as_u64() {
+                total += size;
+                approximate = true;
+            } else {
+                return Ok(FormatFilesize::Unknown);
+            }
+        }
+
+        Ok(if approximate {
+            FormatFilesize::Approximate { bytes: total }
+        } else {
+            FormatFilesize::Exact { bytes: total }
+        })
+    }
+
+    /// Formats available for a single video, as yt-dlp lists them (worst first).
+    pub async fn list_formats(
+        &self,
+        url: &str,
+        extra_args: &[String],
+    ) -> Result<Vec<FormatInfo>, DownloaderError> {
+        let json = self.fetch_full_info(url, extra_args).await?;
+        let str_field = |f: &serde_json::Value, key: &str| f[key].as_str().map(String::from);
+
+        Ok(json["formats"]
+            .as_array()
+            .map(|formats| {
+                formats
+                    .iter()
+                    .filter_map(|f| {
+                        Some(FormatInfo {
+                            format_id: f["format_id"].as_str()?.to_string(),
+                            ext: str_field(f, "ext"),
+                            resolution: str_field(f, "resolution"),
+                            fps: f["fps"].as_f64(),
+                            vcodec: str_field(f, "vcodec"),
+                            acodec: str_field(f, "acodec"),
+                            filesize: f["filesize"].as_u64().or_else(|| f["filesize_approx"].as_u64()),
+                            tbr: f["tbr"].as_f64(),
+                            format_note: str_field(f, "format_note"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Streams flat playlist entries as yt-dlp prints them, returning the total
+    /// count. Triggering `cancel` kills yt-dlp.
+    pub async fn enumerate_playlist<F>(
+        &self,
+        url: &str,
+        extra_args: &[String],
+        cancel: CancellationToken,
+        on_entry: F,
+    ) -> Result<usize, DownloaderError>
+    where
+        F: Fn(usize, PlaylistEntry) + Send + 'static,
+    {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args(["--dump-json", "--flat-playlist", "--no-warnings", "--no-download"])
+            .args(extra_args)
+            .arg(url)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let mut child = cmd.spawn()?;
+
+        // Drained alongside stdout so a full stderr pipe can't stall yt-dlp
+        let stderr = child.stderr.take().unwrap();
+        let stderr_task = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut collected = Vec::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                collected.push(line);
+            }
+            collected
+        });
+
+        let stdout = child.stdout.take().unwrap();
+        let reader = BufReader::new(stdout);
+        let mut lines = reader.lines();
+        let mut count = 0;
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line,
+                _ = cancel.cancelled() => {
+                    terminate(&mut child).await;
+                    return Err(DownloaderError::Cancelled);
+                }
+            };
+            let Ok(Some(line)) = line else {
+                break;
+            };
+            if let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) {
+                on_entry(count, PlaylistEntry::from_flat_json(&entry));
+                count += 1;
+            }
+        }
+
+        let status = child.wait().await?;
+        let stderr = stderr_task.await.unwrap_or_default().join("\n");
+
+        if !status.success() && count == 0 {
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
+        }
+
+        Ok(count)
+    }
+
+    /// Full metadata for the playlist entries at `indices` (1-based, as in
+    /// `playlist_items`), fetched a few at a time. Entries whose full info
+    /// can't be fetched keep their flat data.
+    pub async fn get_playlist_details(
+        &self,
+        url: &str,
+        indices: &[usize],
+        extra_args: &[String],
+    ) -> Result<Vec<PlaylistEntry>, DownloaderError> {
+        if indices.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let items = indices
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args([
+            "--dump-json",
+            "--flat-playlist",
+            "--no-warnings",
+            "--no-download",
+            "--playlist-items",
+            &items,
+        ])
+        .args(extra_args)
+        .arg(url);
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let output = cmd.output().await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let flat: Vec<serde_json::Value> = stdout
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        let semaphore = Semaphore::new(PLAYLIST_DETAILS_CONCURRENCY);
+        let fetches = flat.iter().map(|entry| {
+            let semaphore = &semaphore;
+            async move {
+                let fallback = PlaylistEntry::from_flat_json(entry);
+                let Some(entry_url) = entry["url"]
+                    .as_str()
+                    .or_else(|| entry["webpage_url"].as_str())
+                else {
+                    return fallback;
+                };
+
+                let _permit = semaphore.acquire().await;
+                match self.fetch_full_info(entry_url, extra_args).await {
+                    Ok(json) => PlaylistEntry {
+                        id: json["id"].as_str().map_or(fallback.id, |s| s.to_string()),
+                        title: json["title"].as_str().map_or(fallback.title, |s| s.to_string()),
+                        duration: json["duration"].as_f64().or(fallback.duration),
+                        thumbnail: json["thumbnail"]
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .or(fallback.thumbnail),
+                        uploader: json["uploader"]
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .or(fallback.uploader),
+                    },
+                    Err(_) => fallback,
+                }
+            }
+        });
+
+        Ok(futures_util::future::join_all(fetches).await)
+    }
+
+    /// Validates `options`, runs the probes and info fetches the arguments
+    /// depend on, and builds the yt-dlp arguments shared by real and simulated
+    /// downloads.
+    async fn build_args(&self, options: &DownloadOptions) -> Result<DownloadArgs, DownloaderError> {
+        if let Some(categories) = &options.sponsorblock_remove {
+            if let Some(unknown) = categories
+                .iter()
+                .find(|c| !sponsorblock::is_known_category(c))
+            {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Unknown SponsorBlock category: {}",
+                    unknown
+                )));
+            }
+            if !categories.is_empty() && !self.manager.is_ffmpeg_installed() {
+                return Err(DownloaderError::ExecutionError(
+                    "Removing SponsorBlock segments requires ffmpeg".to_string(),
+                ));
+            }
+        }
+
+        if let DownloadMode::Audio { quality: Some(quality), .. } = &options.mode {
+            let quality_regex = Regex::new(r"^(?:[0-9]|\d{1,4}[Kk])$").unwrap();
+            if !quality_regex.is_match(quality) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid audio quality: {} (expected 0-9 or a bitrate like 192K)",
+                    quality
+                )));
+            }
+        }
+
+        if let DownloadMode::Audio { audio_lang: Some(lang), .. } = &options.mode {
+            let lang_regex = Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]+)*$").unwrap();
+            if !lang_regex.is_match(lang) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid audio language: {}",
+                    lang
+                )));
+            }
+        }
+
+        if options.dual_audio_output && !self.manager.is_ffmpeg_installed() {
+            return Err(DownloaderError::ExecutionError(
+                "Creating an additional MP3 copy requires ffmpeg".to_string(),
+            ));
+        }
+
+        let burn_lang = options.burn_subtitles.as_ref().filter(|l| !l.is_empty());
+        let mut burn_auto_only = false;
+        if let Some(lang) = burn_lang {
+            if !matches!(options.mode, DownloadMode::Video { .. }) || options.no_merge {
+                return Err(DownloaderError::ExecutionError(
+                    "Burning subtitles requires merged video output".to_string(),
+                ));
+            }
+            if !self.manager.is_ffmpeg_installed() {
+                return Err(DownloaderError::ExecutionError(
+                    "Burning subtitles requires ffmpeg".to_string(),
+                ));
+            }
+
+            let info = self.load_info(options).await?;
+            let has_lang = |key: &str| info[key].get(lang.as_str()).is_some();
+            if !has_lang("subtitles") {
+                if !has_lang("automatic_captions") {
+                    return Err(DownloaderError::ExecutionError(format!(
+                        "No subtitles available in language: {}",
+                        lang
+                    )));
+                }
+                burn_auto_only = true;
+            }
+        }
+
+        if options.embed_source_url && !self.manager.is_ffmpeg_installed() {
+            return Err(DownloaderError::ExecutionError(
+                "Embedding the source URL requires ffmpeg".to_string(),
+            ));
+        }
+
+        if options.embed_metadata && !self.manager.is_ffmpeg_installed() {
+            return Err(DownloaderError::ExecutionError(
+                "Embedding metadata and chapters requires ffmpeg".to_string(),
+            ));
+        }
+
+        if options.embed_thumbnail && !self.manager.is_ffmpeg_installed() {
+            return Err(DownloaderError::ExecutionError(
+                "Embedding thumbnails requires ffmpeg".to_string(),
+            ));
+        }
+
+        if options.thumbnail_index.is_some() && !options.embed_thumbnail {
+            return Err(DownloaderError::ExecutionError(
+                "Selecting a thumbnail requires embed_thumbnail".to_string(),
+            ));
+        }
+
+        if let DownloadMode::Video {
+            container: container @ (VideoContainer::Mov | VideoContainer::Avi),
+            ..
+        } = &options.mode
+        {
+            if !options.no_merge && !self.manager.is_ffmpeg_installed() {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Saving as {} requires ffmpeg",
+                    if matches!(container, VideoContainer::Mov) { "MOV" } else { "AVI" }
+                )));
+            }
+        }
+
+        if let Some(downloader) = &options.external_downloader {
+            if downloader != "aria2c" {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Unsupported external downloader: {} (only aria2c is supported)",
+                    downloader
+                )));
+            }
+            if !self.manager.is_aria2c_installed() {
+                return Err(DownloaderError::ExecutionError(
+                    "aria2c was not found on PATH. Install aria2 or turn off the external downloader."
+                        .to_string(),
+                ));
+            }
+        }
+
+        if let Some(on_existing) = &options.on_existing {
+            if !matches!(on_existing.as_str(), "skip" | "overwrite" | "rename") {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Unknown existing file behavior: {} (expected skip, overwrite or rename)",
+                    on_existing
+                )));
+            }
+        }
+
+        if let Some(order) = &options.playlist_order {
+            if !matches!(order.as_str(), "reverse" | "random") {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Unknown playlist order: {} (expected reverse or random)",
+                    order
+                )));
+            }
+        }
+
+        if let Some(format) = &options.sub_format {
+            if !is_valid_sub_format(format) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid subtitle format: {} (expected e.g. srt or vtt/best)",
+                    format
+                )));
+            }
+        }
+
+        if options.no_merge && options.embed_subs {
+            return Err(DownloaderError::ExecutionError(
+                "Embedding subtitles requires merged output".to_string(),
+            ));
+        }
+
+        if let Some(expr) = &options.retry_sleep {
+            if !is_valid_retry_sleep(expr) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid retry sleep expression: {}",
+                    expr
+                )));
+            }
+        }
+
+        if let Some(retries) = &options.fragment_retries {
+            if retries != "infinite" && retries.parse::<u32>().is_err() {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Fragment retries must be a number or \"infinite\": {}",
+                    retries
+                )));
+            }
+        }
+
+        if let Some(sections) = &options.download_sections {
+            if !is_valid_download_sections(sections) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid time range: {} (expected e.g. *00:10:00-00:20:00)",
+                    sections
+                )));
+            }
+            if !self.manager.is_ffmpeg_installed() {
+                return Err(DownloaderError::ExecutionError(
+                    "Downloading a time range requires ffmpeg".to_string(),
+                ));
+            }
+        }
+
+        if let Some(length) = options.trim_filenames {
+            if length < MIN_TRIM_FILENAMES {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Filenames can't be trimmed below {} characters",
+                    MIN_TRIM_FILENAMES
+                )));
+            }
+        }
+
+        if let Some(rate) = &options.rate_limit {
+            if !is_valid_rate_limit(rate) {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Invalid rate limit: {} (expected e.g. 500K or 2M)",
+                    rate
+                )));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (options.min_duration, options.max_duration) {
+            if min > max {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Minimum duration ({}s) is greater than maximum duration ({}s)",
+                    min, max
+                )));
+            }
+        }
+
+        if let Some(pairs) = &options.postprocessor_args {
+            if let Some((name, _)) = pairs
+                .iter()
+                .find(|(name, _)| !is_valid_postprocessor_name(name))
+            {
+                return Err(DownloaderError::ExecutionError(format!(
+                    "Unknown postprocessor: {}",
+                    name
+                )));
+            }
+        }
+
+        if options.use_netrc {
+            if let Some(path) = &options.netrc_path {
+                if !std::path::Path::new(path).is_file() {
+                    return Err(DownloaderError::ExecutionError(format!(
+                        "netrc file not found: {}",
+                        path
+                    )));
+                }
+            }
+        }
+
+        let network_args = connection_args(options)?;
+
+        // yt-dlp embeds its preferred thumbnail, so choosing one means handing it
+        // an info.json whose thumbnail list holds only the selected entry.
+        // Written once everything is validated; the guard removes it on any error
+        let thumbnail_info = match options.thumbnail_index {
+            Some(index) => Some(self.write_thumbnail_info(options, index).await?),
+            None => None,
+        };
+        let info_json_path = match &thumbnail_info {
+            Some(file) => Some(file.0.to_string_lossy().to_string()),
+            None => options.load_info_json.clone(),
+        };
+
+        let mut resolved = ResolvedInputs {
+            smart_container: None,
+            write_auto_subs: burn_auto_only || options.write_auto_subs,
+            info_json_path,
+        };
+
+        // Languages that only exist as auto-captions are skipped without --write-auto-subs;
+        // yt-dlp still prefers real subtitles where both exist
+        if (options.embed_subs || options.write_subs_external) && !resolved.write_auto_subs {
+            if let Some(langs) = options.sub_langs.as_ref().filter(|l| !l.is_empty()) {
+                let info = self.load_info(options).await.ok();
+                resolved.write_auto_subs |= needs_auto_captions(info.as_ref(), langs);
+            }
+        }
+
+        if let DownloadMode::Video {
+            quality,
+            container: VideoContainer::SmartMp4,
+            video_codec,
+        } = &options.mode
+        {
+            if !options.no_merge {
+                let selector = video_format_selector(options, quality, video_codec.as_deref())?;
+                resolved.smart_container =
+                    Some(self.probe_smart_container(options, &selector, &network_args).await);
+            }
+        }
+
+        let args = build_download_args(options, &self.manager, &resolved)?;
+
+        // Don't let an old binary choke on a newer flag, or reject a hidden alias
+        let (args, dropped_flags) = self.manager.adapt_to_installed(args);
+
+        Ok(DownloadArgs {
+            args,
+            dropped_flags,
+            network_args,
+            thumbnail_info,
+        })
+    }
+
+    /// Predicts the files and formats a download would produce, without
+    /// downloading anything.
+    pub async fn simulate_download(
+        &self,
+        options: &DownloadOptions,
+    ) -> Result<Vec<SimulatedFile>, DownloaderError> {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let DownloadArgs {
+            args,
+            thumbnail_info,
+            ..
+        } = self.build_args(options).await?;
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args(["--simulate", "--no-warnings", "--print", "filename", "--print", "format"])
+            .args(&args);
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let output = cmd.output().await;
+        drop(thumbnail_info);
+        let output = output?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
+        }
+
+        // Each entry prints its filename, then its format
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().filter(|l| !l.trim().is_empty()).collect();
+        Ok(lines
+            .chunks(2)
+            .map(|pair| SimulatedFile {
+                filename: pair[0].trim().to_string(),
+                format: pair.get(1).map(|f| f.trim().to_string()).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Runs yt-dlp for `options`. Triggering `cancel` kills the process, leaving
+    /// any `.part` files on disk so the download can be resumed later.
+    pub async fn download<F>(
+        &self,
+        options: &DownloadOptions,
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<String, DownloaderError>
+    where
+        F: Fn(DownloadProgress) + Send + 'static,
+    {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let DownloadArgs {
+            args: built_args,
+            network_args,
+            thumbnail_info,
+            dropped_flags,
+        } = self.build_args(options).await?;
+        let burn_lang = options.burn_subtitles.as_ref().filter(|l| !l.is_empty());
+
+        // Ensure output directory exists
+        let output_path = std::path::Path::new(&options.output_dir);
+        if !output_path.exists() {
+            std::fs::create_dir_all(output_path)?;
+        }
+
+        let mut args = vec![
+            "--progress".to_string(),
             "--newline".to_string(),
             "--force-progress".to_string(), // Windows에서 비터미널 환경에서도 진행 상태 출력
-            "-o".to_string(),
-            output_template,
+            "--progress-template".to_string(),
+            PROGRESS_TEMPLATE.to_string(),
+            "--continue".to_string(), // 일시정지 후 재개 시 .part 파일에서 이어받기
         ];
+        args.extend(built_args);
+
+        // Warn up front if the cached info shows nothing within the height bounds
+        let mut warnings = Vec::new();
+        if matches!(options.mode, DownloadMode::Video { .. })
+            && (options.min_height.is_some() || options.max_height.is_some())
+        {
+            let info_path = options
+                .load_info_json
+                .as_ref()
+                .map(PathBuf::from)
+                .or_else(|| self.cached_info_json(&options.url));
+            if let Some(false) = info_path
+                .and_then(|p| info_has_height_in_range(&p, options.min_height, options.max_height))
+            {
+                warnings.push(
+                    "No format matches the requested resolution range; downloading the closest available"
+                        .to_string(),
+                );
+            }
+        }
+        if !dropped_flags.is_empty() {
+            warnings.push(format!(
+                "The installed yt-dlp doesn't support {}; downloading without it. Update yt-dlp to use it",
+                dropped_flags.join(", ")
+            ));
+        }
+        if burn_lang.is_some() {
+            warnings.push(
+                "Burning subtitles re-encodes the whole video; this can take a long time and slightly reduces quality"
+                    .to_string(),
+            );
+        }
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let started_at = Instant::now();
+        let mut child = cmd.spawn()?;
+
+        // Emit starting status immediately
+        on_progress(DownloadProgress {
+            status: "starting".to_string(),
+            percentage: Some(0.0),
+            warning: (!warnings.is_empty()).then(|| warnings.join("; ")),
+            pid: child.id(),
+            ..Default::default()
+        });
+
+        // Drain stderr concurrently so a chatty process can't block on a full pipe.
+        // Live streams recorded through ffmpeg report progress there, redrawing
+        // with carriage returns, so segments are split on those too
+        let stderr = child.stderr.take().unwrap();
+        let (recording_tx, mut recording_rx) = tokio::sync::mpsc::unbounded_channel();
+        let stderr_task = tokio::spawn(async move {
+            let mut segments = BufReader::new(stderr).split(b'\r');
+            let mut collected = Vec::new();
+            while let Ok(Some(segment)) = segments.next_segment().await {
+                for line in String::from_utf8_lossy(&segment).lines() {
+                    match parse_ffmpeg_recording_line(line) {
+                        Some(progress) => {
+                            let _ = recording_tx.send(progress);
+                        }
+                        None if !line.trim().is_empty() => collected.push(line.to_string()),
+                        None => {}
+                    }
+                }
+            }
+            collected
+        });
+
+        let stdout = child.stdout.take().unwrap();
+        let reader = BufReader::new(stdout);
+        let mut lines = reader.lines();
+
+        let merger_regex = Regex::new(r#"\[Merger\] Merging formats into "(.+)""#).unwrap();
+        let thumbnail_regex = Regex::new(r"Writing video thumbnail \S+ to: (.+)$").unwrap();
+        let existing_regex = Regex::new(r"^\[download\] (.+) has already been downloaded").unwrap();
+
+        // Files of the current item still waiting on a merge, and finished outputs
+        let mut pending_files: Vec<String> = Vec::new();
+        let mut output_files: Vec<String> = Vec::new();
+
+        // Size of each finished stream plus the one in progress, for the summary
+        let mut finished_bytes: u64 = 0;
+        let mut current_total: Option<u64> = None;
+        let mut last_title: Option<String> = None;
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line,
+                Some(progress) = recording_rx.recv() => {
+                    on_progress(progress);
+                    continue;
+                }
+                _ = cancel.cancelled() => {
+                    terminate(&mut child).await;
+                    return Err(DownloaderError::Cancelled);
+                }
+            };
+            let Ok(Some(line)) = line else {
+                break;
+            };
+
+            // Thumbnail mode never prints a download destination
+            if let Some(caps) = thumbnail_regex.captures(&line) {
+                if matches!(options.mode, DownloadMode::Thumbnail) {
+                    // Converted to JPEG after writing
+                    let path = PathBuf::from(caps[1].trim()).with_extension("jpg");
+                    pending_files.push(path.to_string_lossy().to_string());
+                }
+                continue;
+            }
+
+            // Detect video info extraction phase
+            if line.starts_with("[youtube]") || line.starts_with("[info]") || line.contains("Extracting") {
+                on_progress(DownloadProgress {
+                    status: "extracting".to_string(),
+                    percentage: Some(0.0),
+                    ..Default::default()
+                });
+                continue;
+            }
+            let progress = parse_progress_line(&line).or_else(|| parse_aria2c_progress_line(&line));
+            if let Some(progress) = progress {
+                current_total = progress.total_bytes.or(current_total);
+                if progress.title.is_some() {
+                    last_title = progress.title.clone();
+                }
+                on_progress(progress);
+            } else if let Some(caps) = existing_regex.captures(&line) {
+                let filename = caps[1].trim().to_string();
+                pending_files.push(filename.clone());
+                on_progress(DownloadProgress {
+                    status: "skipped".to_string(),
+                    percentage: Some(100.0),
+                    filename: Some(filename),
+                    ..Default::default()
+                });
+            } else if line.contains("[download] Destination:") {
+                let filename = line.replace("[download] Destination:", "").trim().to_string();
+                pending_files.push(filename.clone());
+                finished_bytes += current_total.take().unwrap_or(0);
+                on_progress(DownloadProgress {
+                    status: "starting".to_string(),
+                    percentage: Some(0.0),
+                    filename: Some(filename),
+                    ..Default::default()
+                });
+            } else if let Some(status) = postprocessor_status(&line) {
+                if let Some(caps) = merger_regex.captures(&line) {
+                    pending_files.clear();
+                    output_files.push(caps[1].to_string());
+                } else if line.contains("[ExtractAudio] Destination:") {
+                    // The source file is replaced by the extracted audio, unless kept
+                    if !matches!(options.mode, DownloadMode::Audio { keep_video: true, .. }) {
+                        pending_files.pop();
+                    }
+                    pending_files.push(
+                        line.replace("[ExtractAudio] Destination:", "").trim().to_string(),
+                    );
+                }
+                on_progress(DownloadProgress {
+                    status: status.to_string(),
+                    percentage: Some(100.0),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let status = child.wait().await?;
+        drop(thumbnail_info);
+        // Measured before post-processing so the average reflects network speed
+        let elapsed_secs = started_at.elapsed().as_secs_f64();
+        let stderr_output = stderr_task.await.unwrap_or_default().join("\n");
+
+        if status.success() {
+            output_files.append(&mut pending_files);
+
+            if options.dual_audio_output && matches!(options.mode, DownloadMode::Audio { .. }) {
+                on_progress(DownloadProgress {
+                    status: "converting".to_string(),
+                    percentage: Some(100.0),
+                    ..Default::default()
+                });
+
+                let mut copies = Vec::new();
+                for file in &output_files {
+                    let path = std::path::Path::new(file);
+                    // Videos kept with keep_video already have their audio converted
+                    if path.extension().and_then(|e| e.to_str()) == Some("mp3")
+                        || has_extension(file, VIDEO_EXTENSIONS)
+                    {
+                        continue;
+                    }
+                    copies.push(self.convert_to_mp3(path).await?.to_string_lossy().to_string());
+                }
+                output_files.extend(copies);
+            }
+
+            if let Some(lang) = burn_lang {
+                let videos: Vec<String> = output_files
+                    .iter()
+                    .filter(|f| has_extension(f, VIDEO_EXTENSIONS))
+                    .cloned()
+                    .collect();
+                for video in videos {
+                    let video = std::path::Path::new(&video);
+                    let subtitle = find_subtitle_file(video, lang).ok_or_else(|| {
+                        DownloaderError::DownloadFailed(format!(
+                            "Subtitle file for language {} was not downloaded",
+                            lang
+                        ))
+                    })?;
+
+                    let burned = self.burn_subtitles(video, &subtitle, &cancel, &on_progress).await?;
+                    output_files.push(burned.to_string_lossy().to_string());
+
+                    // The subtitle file was only fetched for burning
+                    if !options.write_subs_external {
+                        let _ = std::fs::remove_file(&subtitle);
+                    }
+                }
+            }
+
+            if options.write_manifest {
+                let info = self.get_video_info(&options.url, &network_args).await.ok();
+                let manifest = DownloadManifest::new(
+                    options,
+                    info.as_ref().map(|i| i.id.clone()),
+                    info.map(|i| i.title),
+                    &output_files,
+                );
+                // The media itself is already on disk; a manifest failure shouldn't fail the download
+                let _ = manifest.write();
+            }
+
+            let mirror_results = match &options.mirror_dirs {
+                Some(dirs) if !dirs.is_empty() => {
+                    Some(mirror_files(&output_files, dirs, &on_progress).await)
+                }
+                _ => None,
+            };
+
+            let total_bytes = finished_bytes + current_total.unwrap_or(0);
+
+            let container = output_files.last().and_then(|f| {
+                std::path::Path::new(f)
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+            });
+
+            on_progress(DownloadProgress {
+                status: "completed".to_string(),
+                percentage: Some(100.0),
+                container,
+                filename: output_files.last().cloned(),
+                title: last_title,
+                output_files: Some(output_files),
+                mirror_results,
+                total_bytes: (total_bytes > 0).then_some(total_bytes),
+                average_speed_bps: (total_bytes > 0 && elapsed_secs > 0.0)
+                    .then(|| (total_bytes as f64 / elapsed_secs) as u64),
+                elapsed_secs: Some(elapsed_secs),
+                ..Default::default()
+            });
+            Ok(options.output_dir.clone())
+        } else if let Some(message) = detect_postprocessing_failure(&stderr_output) {
+            output_files.append(&mut pending_files);
+            output_files.retain(|f| std::path::Path::new(f).exists());
+            Err(DownloaderError::PostProcessingFailed {
+                message,
+                files: output_files,
+            })
+        } else {
+            Err(failure_from_stderr(&stderr_output, "Download process failed"))
+        }
+    }
+
+    /// Runs a throwaway `--simulate` so yt-dlp's Python imports and extractor
+    /// caches are warm before the first real download. Returns whether it succeeded.
+    pub async fn warm_extractor(&self, url: &str) -> Result<bool, DownloaderError> {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args(["--simulate", "--no-playlist", "--quiet", "--no-warnings", url])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        Ok(cmd.status().await?.success())
+    }
+
+    /// Starts a download tuned for playing while it downloads and returns the
+    /// file path once `STREAM_BUFFER_BYTES` are on disk; the rest keeps
+    /// downloading in the background, reported through `on_progress`, which
+    /// ends with a `completed`, `error` or `cancelled` status. Triggering
+    /// `cancel` kills yt-dlp and deletes the file.
+    ///
+    /// Whether the partial file plays depends on the format:
+    /// - progressive MP4: only if the `moov` index is at the front (most sites do this)
+    /// - WebM/MKV: plays as it grows, though seeking ahead of the download fails
+    /// - HLS: written as MPEG-TS, which plays from the first fragment
+    /// - merged selectors (`a+b`): the file only appears after the merge, so
+    ///   nothing is playable until the download finishes
+    pub async fn stream_to_file<F>(
+        &self,
+        url: &str,
+        format: Option<&str>,
+        extra_args: &[String],
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<PathBuf, DownloaderError>
+    where
+        F: Fn(DownloadProgress) + Send + 'static,
+    {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+
+        let stream_dir = stream_dir();
+        std::fs::create_dir_all(&stream_dir)?;
+        let output_template = stream_dir
+            .join("%(title)s [%(id)s].%(ext)s")
+            .to_string_lossy()
+            .to_string();
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args([
+            "--progress",
+            "--newline",
+            "--force-progress",
+            "--no-playlist",
+            "-f",
+            format.unwrap_or(STREAM_DEFAULT_FORMAT),
+            // Write straight to the final name so players can open it right away
+            "--no-part",
+            "--hls-use-mpegts",
+            "--concurrent-fragments",
+            "4",
+            "-o",
+            &output_template,
+        ]);
+        if self.manager.is_ffmpeg_installed() {
+            cmd.arg("--ffmpeg-location").arg(self.manager.get_ffmpeg_path());
+        }
+        cmd.args(extra_args)
+            .arg(url)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let mut child = cmd.spawn()?;
+        let stderr = child.stderr.take().unwrap();
+        let stderr_task = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut collected = Vec::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                collected.push(line);
+            }
+            collected
+        });
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+        let progress_regex = Regex::new(r"\[download\]\s+(\d+\.?\d*)%").unwrap();
+        let existing_regex = Regex::new(r"\[download\] (.+) has already been downloaded").unwrap();
+        let mut destination: Option<PathBuf> = None;
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line?,
+                _ = cancel.cancelled() => {
+                    terminate(&mut child).await;
+                    if let Some(path) = &destination {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    return Err(DownloaderError::Cancelled);
+                }
+            };
+            let Some(line) = line else {
+                break;
+            };
+
+            if let Some(path) = line.strip_prefix("[download] Destination:") {
+                destination = Some(PathBuf::from(path.trim()));
+            } else if let Some(caps) = existing_regex.captures(&line) {
+                let path = PathBuf::from(&caps[1]);
+                on_progress(DownloadProgress {
+                    status: "completed".to_string(),
+                    percentage: Some(100.0),
+                    filename: Some(path.to_string_lossy().to_string()),
+                    ..Default::default()
+                });
+                return Ok(path);
+            } else if let Some(caps) = progress_regex.captures(&line) {
+                on_progress(DownloadProgress {
+                    status: "buffering".to_string(),
+                    percentage: caps[1].parse::<f64>().ok(),
+                    filename: destination.as_ref().map(|p| p.to_string_lossy().to_string()),
+                    ..Default::default()
+                });
+            }
+
+            let buffered = destination
+                .as_ref()
+                .and_then(|p| std::fs::metadata(p).ok())
+                .map(|m| m.len() >= STREAM_BUFFER_BYTES)
+                .unwrap_or(false);
+            if buffered {
+                let path = destination.clone().unwrap();
+                let partial_path = path.clone();
+                let filename = path.to_string_lossy().to_string();
+                tokio::spawn(async move {
+                    loop {
+                        let line = tokio::select! {
+                            line = lines.next_line() => line,
+                            _ = cancel.cancelled() => {
+                                terminate(&mut child).await;
+                                let _ = std::fs::remove_file(&partial_path);
+                                on_progress(DownloadProgress {
+                                    status: "cancelled".to_string(),
+                                    filename: Some(filename),
+                                    ..Default::default()
+                                });
+                                return;
+                            }
+                        };
+                        let Ok(Some(line)) = line else {
+                            break;
+                        };
+                        if let Some(caps) = progress_regex.captures(&line) {
+                            on_progress(DownloadProgress {
+                                status: "downloading".to_string(),
+                                percentage: caps[1].parse::<f64>().ok(),
+                                filename: Some(filename.clone()),
+                                ..Default::default()
+                            });
+                        }
+                    }
+
+                    let success = matches!(child.wait().await, Ok(status) if status.success());
+                    let stderr = stderr_task.await.unwrap_or_default().join("\n");
+                    let progress = if success {
+                        DownloadProgress {
+                            status: "completed".to_string(),
+                            percentage: Some(100.0),
+                            filename: Some(filename),
+                            ..Default::default()
+                        }
+                    } else {
+                        let error = failure_from_stderr(&stderr, "Stream download failed");
+                        DownloadProgress {
+                            status: "error".to_string(),
+                            filename: Some(error.to_string()),
+                            error_category: Some(error.category().to_string()),
+                            ..Default::default()
+                        }
+                    };
+                    on_progress(progress);
+                });
+                return Ok(path);
+            }
+        }
+
+        // Finished before reaching the buffer threshold (short clip)
+        let status = child.wait().await?;
+        let stderr = stderr_task.await.unwrap_or_default().join("\n");
+        match destination {
+            Some(path) if status.success() && path.exists() => {
+                on_progress(DownloadProgress {
+                    status: "completed".to_string(),
+                    percentage: Some(100.0),
+                    filename: Some(path.to_string_lossy().to_string()),
+                    ..Default::default()
+                });
+                Ok(path)
+            }
+            destination => {
+                if let Some(path) = destination {
+                    let _ = std::fs::remove_file(path);
+                }
+                Err(failure_from_stderr(&stderr, "Stream download failed"))
+            }
+        }
+    }
+
+    /// Grabs a single frame at `timestamp` by downloading a short clip around it
+    /// and extracting the first frame with ffmpeg. Returns the PNG path.
+    pub async fn capture_frame(
+        &self,
+        url: &str,
+        timestamp: &str,
+        output_dir: &str,
+    ) -> Result<PathBuf, DownloaderError> {
+        if !self.manager.is_ytdlp_installed() {
+            return Err(DownloaderError::BinaryNotFound);
+        }
+        if !self.manager.is_ffmpeg_installed() {
+            return Err(DownloaderError::ExecutionError(
+                "Capturing a frame requires ffmpeg".to_string(),
+            ));
+        }
+
+        let seconds = parse_timestamp(timestamp).ok_or_else(|| {
+            DownloaderError::ExecutionError(format!(
+                "Invalid timestamp: {} (expected SS, MM:SS or HH:MM:SS)",
+                timestamp
+            ))
+        })?;
+
+        // Each capture gets its own scratch directory so the clip is easy to find
+        let work_dir = std::env::temp_dir()
+            .join("yt-dlp-gui")
+            .join("frames")
+            .join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&work_dir)?;
+
+        let result = self
+            .capture_frame_in(url, timestamp, seconds, output_dir, &work_dir)
+            .await;
+        let _ = std::fs::remove_dir_all(&work_dir);
+        result
+    }
+
+    async fn capture_frame_in(
+        &self,
+        url: &str,
+        timestamp: &str,
+        seconds: f64,
+        output_dir: &str,
+        work_dir: &std::path::Path,
+    ) -> Result<PathBuf, DownloaderError> {
+        let clip_template = work_dir.join("%(title)s.%(ext)s").to_string_lossy().to_string();
+        let section = format!("*{}-{}", seconds, seconds + 1.0);
+
+        let mut cmd = Command::new(self.manager.get_ytdlp_path());
+        cmd.args([
+            "--no-warnings",
+            "--no-playlist",
+            "-f",
+            "bv*/b",
+            "--download-sections",
+            &section,
+            "--force-keyframes-at-cuts",
+            "--ffmpeg-location",
+            &self.manager.get_ffmpeg_path().to_string_lossy(),
+            "-o",
+            &clip_template,
+            url,
+        ]);
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let output = cmd.output().await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(classify_stderr(&stderr).unwrap_or(DownloaderError::ExecutionError(stderr)));
+        }
+
+        let clip_path = std::fs::read_dir(work_dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|path| path.is_file())
+            .ok_or_else(|| DownloaderError::DownloadFailed("No clip was downloaded".to_string()))?;
+
+        let title = clip_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "frame".to_string());
+        let output_path = std::path::Path::new(output_dir);
+        std::fs::create_dir_all(output_path)?;
+        let image_path = output_path.join(format!("{} [{}].png", title, timestamp.replace(':', "-")));
+
+        let mut ffmpeg = Command::new(self.manager.get_ffmpeg_path());
+        ffmpeg
+            .arg("-y")
+            .arg("-i")
+            .arg(&clip_path)
+            .args(["-frames:v", "1"])
+            .arg(&image_path);
+
+        #[cfg(target_os = "windows")]
+        ffmpeg.creation_flags(CREATE_NO_WINDOW);
+
+        let output = ffmpeg.output().await?;
+        if !output.status.success() {
+            return Err(DownloaderError::ExecutionError(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        Ok(image_path)
+    }
+
+    async fn convert_to_mp3(&self, input: &std::path::Path) -> Result<PathBuf, DownloaderError> {
+        let output = input.with_extension("mp3");
+
+        let mut cmd = Command::new(self.manager.get_ffmpeg_path());
+        cmd.arg("-y")
+            .arg("-i")
+            .arg(input)
+            .args(["-vn", "-codec:a", "libmp3lame", "-q:a", "2"])
+            .arg(&output);
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let result = cmd.output().await?;
+        if !result.status.success() {
+            return Err(DownloaderError::ExecutionError(
+                String::from_utf8_lossy(&result.stderr).to_string(),
+            ));
+        }
+
+        Ok(output)
+    }
+
+    /// Re-encodes `video` with `subtitle` rendered into the picture, writing
+    /// `<stem>.burned.<ext>` next to it. Reports `"burning_subtitles"` progress.
+    async fn burn_subtitles<F>(
+        &self,
+        video: &std::path::Path,
+        subtitle: &std::path::Path,
+        cancel: &CancellationToken,
+        on_progress: &F,
+    ) -> Result<PathBuf, DownloaderError>
+    where
+        F: Fn(DownloadProgress),
+    {
+        let video = std::path::absolute(video)?;
+        let ext = video
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mkv".to_string());
+        let stem = video
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let output = video.with_file_name(format!("{}.burned.{}", stem, ext));
+
+        // The subtitles filter takes a filter-syntax path, which breaks on colons,
+        // quotes and brackets; a plain name in ffmpeg's working dir avoids escaping
+        let work_dir = std::env::temp_dir()
+            .join("yt-dlp-gui")
+            .join(format!("burn-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&work_dir)?;
+        let sub_ext = subtitle
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "srt".to_string());
+        let sub_name = format!("subs.{}", sub_ext);
+        std::fs::copy(subtitle, work_dir.join(&sub_name))?;
+
+        let result = self
+            .run_burn_ffmpeg(&video, &sub_name, &output, &work_dir, cancel, on_progress)
+            .await;
+        let _ = std::fs::remove_dir_all(&work_dir);
+
+        if result.is_err() {
+            let _ = std::fs::remove_file(&output);
+        }
+        result.map(|_| output)
+    }
+
+    async fn run_burn_ffmpeg<F>(
+        &self,
+        video: &std::path::Path,
+        sub_name: &str,
+        output: &std::path::Path,
+        work_dir: &std::path::Path,
+        cancel: &CancellationToken,
+        on_progress: &F,
+    ) -> Result<(), DownloaderError>
+    where
+        F: Fn(DownloadProgress),
+    {
+        let codec_args: &[&str] = if output.extension().and_then(|e| e.to_str()) == Some("webm") {
+            &["-c:v", "libvpx-vp9", "-crf", "32", "-b:v", "0", "-row-mt", "1"]
+        } else {
+            &["-c:v", "libx264", "-crf", "20", "-preset", "medium"]
+        };
+
+        let mut cmd = Command::new(self.manager.get_ffmpeg_path());
+        cmd.current_dir(work_dir)
+            .args(["-y", "-nostats", "-progress", "pipe:2", "-i"])
+            .arg(video)
+            .arg("-vf")
+            .arg(format!("subtitles={}", sub_name))
+            .args(codec_args)
+            .args(["-c:a", "copy"])
+            .arg(output)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let mut child = cmd.spawn()?;
+        let mut lines = BufReader::new(child.stderr.take().unwrap()).lines();
+
+        let duration_regex = Regex::new(r"Duration:\s*(\d+:\d+:\d+(?:\.\d+)?)").unwrap();
+        let mut duration = None;
+        let mut tail = Vec::new();
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line?,
+                _ = cancel.cancelled() => {
+                    let _ = child.kill().await;
+                    return Err(DownloaderError::Cancelled);
+                }
+            };
+            let Some(line) = line else { break };
+
+            if duration.is_none() {
+                if let Some(caps) = duration_regex.captures(&line) {
+                    duration = parse_timestamp(&caps[1]);
+                }
+            }
+
+            if let Some(out_us) = line.strip_prefix("out_time_us=") {
+                let elapsed = out_us.trim().parse::<f64>().unwrap_or(0.0) / 1_000_000.0;
+                on_progress(DownloadProgress {
+                    status: "burning_subtitles".to_string(),
+                    percentage: duration
+                        .filter(|d| *d > 0.0)
+                        .map(|d| (elapsed / d * 100.0).clamp(0.0, 100.0)),
+                    filename: Some(output.to_string_lossy().to_string()),
+                    ..Default::default()
+                });
+            } else if !line.contains('=') {
+                tail.push(line);
+                if tail.len() > 20 {
+                    tail.remove(0);
+                }
+            }
+        }
+
+        if !child.wait().await?.success() {
+            return Err(DownloaderError::ExecutionError(tail.join("\n")));
+        }
+        Ok(())
+    }
+
+    pub fn get_manager(&self) -> &YtDlpManager {
+        &self.manager
+    }
+}
 
-        // DownloadMode에 따라 인자 추가
-        match &options.mode {
-            DownloadMode::Video { quality, container } => {
-                args.push("-f".to_string());
-                args.push(quality.to_format_string().to_string());
+/// Copies `files` into each of `dirs`, reporting `"mirroring"` progress per file.
+/// A target stops at its first failed file; the other targets are still attempted.
+async fn mirror_files<F>(files: &[String], dirs: &[String], on_progress: &F) -> Vec<MirrorResult>
+where
+    F: Fn(DownloadProgress),
+{
+    let total = (files.len() * dirs.len()).max(1);
+    let mut results = Vec::new();
+
+    for (dir_index, dir) in dirs.iter().enumerate() {
+        let mut result = MirrorResult {
+            dir: dir.clone(),
+            success: true,
+            error: None,
+            files: Vec::new(),
+        };
+
+        if let Err(e) = tokio::fs::create_dir_all(dir).await {
+            result.success = false;
+            result.error = Some(e.to_string());
+            results.push(result);
+            continue;
+        }
+
+        for (file_index, file) in files.iter().enumerate() {
+            let source = std::path::Path::new(file);
+            let Some(name) = source.file_name() else {
+                continue;
+            };
+            let target = std::path::Path::new(dir).join(name);
+
+            on_progress(DownloadProgress {
+                status: "mirroring".to_string(),
+                percentage: Some(
+                    (dir_index * files.len() + file_index) as f64 / total as f64 * 100.0,
+                ),
+                filename: Some(target.to_string_lossy().to_string()),
+                ..Default::default()
+            });
+
+            match tokio::fs::copy(source, &target).await {
+                Ok(_) => result.files.push(target.to_string_lossy().to_string()),
+                Err(e) => {
+                    result.success = false;
+                    result.error = Some(format!("{}: {}", target.display(), e));
+                    break;
+                }
+            }
+        }
+
+        results.push(result);
+    }
+
+    results
+}
+
+/// Checks the general shape of a `--retry-sleep` value: an optional retry type
+/// prefix, then a fixed number of seconds or a `linear=`/`exp=` expression.
+pub fn is_valid_retry_sleep(expr: &str) -> bool {
+    let re = Regex::new(
+        r"^(?:(?:http|fragment|file_access|extractor):)?(?:\d+(?:\.\d+)?|(?:linear|exp)=\d+(?:\.\d+)?(?::(?:\d+(?:\.\d+)?)?){0,2})$",
+    )
+    .unwrap();
+    re.is_match(expr.trim())
+}
+
+/// Parses a line printed through `PROGRESS_TEMPLATE`. yt-dlp prints `NA` for
+/// unknown fields; the estimated total stands in when the exact one is unknown.
+fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
+    let fields: Vec<&str> = line.trim().strip_prefix(PROGRESS_PREFIX)?.splitn(7, '/').collect();
+    let [downloaded, total, estimate, speed, eta, is_live, title] = fields[..] else {
+        return None;
+    };
+    let number = |s: &str| s.parse::<f64>().ok();
+
+    let downloaded_bytes = number(downloaded).map(|b| b as u64);
+    let total_bytes = number(total).or_else(|| number(estimate)).map(|b| b as u64);
+    let percentage = match (downloaded_bytes, total_bytes) {
+        (Some(done), Some(total)) if total > 0 => {
+            Some((done as f64 / total as f64 * 100.0).min(100.0))
+        }
+        _ => None,
+    };
+
+    // A live stream has no total until it ends
+    let status = if is_live == "True" && total_bytes.is_none() {
+        "recording"
+    } else {
+        "downloading"
+    };
+
+    Some(DownloadProgress {
+        status: status.to_string(),
+        percentage,
+        speed: number(speed).map(format_speed),
+        eta: number(eta).map(|s| format_eta(s as u64)),
+        total_bytes,
+        downloaded_bytes,
+        title: Some(title.to_string()).filter(|t| t != "NA" && !t.is_empty()),
+        ..Default::default()
+    })
+}
+
+/// Parses ffmpeg's stats line, e.g. `size=    1024KiB time=00:01:00.00 bitrate=...`,
+/// which it prints while yt-dlp records a live stream through it.
+fn parse_ffmpeg_recording_line(line: &str) -> Option<DownloadProgress> {
+    let re = Regex::new(r"size=\s*(\d+)([kKM])i?B\s+time=\s*(\S+)").unwrap();
+    let caps = re.captures(line)?;
+    let multiplier = if &caps[2] == "M" { 1024 * 1024 } else { 1024 };
+
+    Some(DownloadProgress {
+        status: "recording".to_string(),
+        downloaded_bytes: caps[1].parse::<u64>().ok().map(|size| size * multiplier),
+        recorded_secs: parse_timestamp(&caps[3]),
+        ..Default::default()
+    })
+}
+
+/// Status for a yt-dlp post-processor line, so the UI can show which phase is
+/// running instead of a generic "processing".
+fn postprocessor_status(line: &str) -> Option<&'static str> {
+    if line.starts_with("[Merger]") {
+        Some("merging")
+    } else if line.starts_with("[ExtractAudio]") {
+        Some("extracting_audio")
+    } else if line.starts_with("[VideoConvertor]") || line.starts_with("[VideoRemuxer]") {
+        Some("converting")
+    } else {
+        None
+    }
+}
+
+/// Parses an aria2c readout like `[#2089b0 400.0KiB/33.2MiB(1%) CN:16 DL:115.7KiB ETA:4m51s]`,
+/// which yt-dlp passes through instead of its own progress lines.
+fn parse_aria2c_progress_line(line: &str) -> Option<DownloadProgress> {
+    let re = Regex::new(
+        r"\[#\w+ ([\d.]+)([KMGT]?i?B)/([\d.]+)([KMGT]?i?B)\((\d+)%\)(?: CN:\d+)?(?: DL:([\d.]+)([KMGT]?i?B))?(?: ETA:(\w+))?\]",
+    )
+    .unwrap();
+    // aria2c redraws with carriage returns; only the last readout matters
+    let caps = re.captures_iter(line).last()?;
 
+    let bytes = |value: &str, unit: &str| {
+        let multiplier = match unit.chars().next() {
+            Some('K') => 1024f64,
+            Some('M') => 1024f64.powi(2),
+            Some('G') => 1024f64.powi(3),
+            Some('T') => 1024f64.powi(4),
+            _ => 1.0,
+        };
+        value.parse::<f64>().ok().map(|v| (v * multiplier) as u64)
+    };
+
+    Some(DownloadProgress {
+        status: "downloading".to_string(),
+        percentage: caps[5].parse().ok(),
+        speed: caps
+            .get(6)
+            .zip(caps.get(7))
+            .and_then(|(v, u)| bytes(v.as_str(), u.as_str()))
+            .map(|b| format_speed(b as f64)),
+        eta: caps.get(8).and_then(|m| parse_aria2c_eta(m.as_str())).map(format_eta),
+        total_bytes: bytes(&caps[3], &caps[4]),
+        downloaded_bytes: bytes(&caps[1], &caps[2]),
+        ..Default::default()
+    })
+}
+
+/// Parses aria2c's ETA, e.g. `1h2m3s` or `45s`, into seconds.
+fn parse_aria2c_eta(eta: &str) -> Option<u64> {
+    let re = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap();
+    let caps = re.captures(eta)?;
+    let part = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u64>().ok()).unwrap_or(0);
+    Some(part(1) * 3600 + part(2) * 60 + part(3))
+}
+
+/// Formats bytes per second the way yt-dlp does, e.g. `1.50MiB/s`.
+fn format_speed(bytes_per_sec: f64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes_per_sec;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}/s", value, UNITS[unit])
+}
+
+fn format_eta(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+/// Asks the child to exit (SIGTERM on Unix) so yt-dlp can flush its `.part`
+/// file, killing it if it doesn't within `TERMINATE_GRACE`.
+async fn terminate(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()
+            .await;
+        if tokio::time::timeout(TERMINATE_GRACE, child.wait()).await.is_ok() {
+            return;
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// An output template must stay inside the output directory and keep the extension.
+pub fn is_valid_output_template(template: &str) -> bool {
+    let path = std::path::Path::new(template);
+    template.contains("%(ext)s")
+        && !path.is_absolute()
+        && !template.starts_with(['/', '\\'])
+        && !path
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        && !template.split(['/', '\\']).any(|part| part == "..")
+}
+
+/// Checks a `--limit-rate` value: a number of bytes with an optional K/M/G suffix.
+pub fn is_valid_rate_limit(rate: &str) -> bool {
+    Regex::new(r"^\d+(\.\d+)?[KMG]?$").unwrap().is_match(rate.trim())
+}
+
+/// Builds the yt-dlp arguments for `options`, ending with the URL (or info
+/// JSON) to fetch. Probe and info-fetch results come in through `resolved`, so
+/// this never spawns a process.
+pub fn build_download_args(
+    options: &DownloadOptions,
+    manager: &YtDlpManager,
+    resolved: &ResolvedInputs,
+) -> Result<Vec<String>, DownloaderError> {
+    let is_playlist = matches!(
+        classify_url(&options.url).kind,
+        UrlKind::Playlist | UrlKind::Channel
+    );
+    let template = if options.organize_playlist && is_playlist {
+        PLAYLIST_OUTPUT_TEMPLATE
+    } else {
+        options
+            .output_template
+            .as_deref()
+            .unwrap_or("%(title)s.%(ext)s")
+    };
+    if !is_valid_output_template(template) {
+        return Err(DownloaderError::ExecutionError(format!(
+            "Invalid output template: {} (must be a relative path containing %(ext)s)",
+            template
+        )));
+    }
+
+    // Build output template with proper path separator
+    let output_template = std::path::Path::new(&options.output_dir)
+        .join(template)
+        .to_string_lossy()
+        .to_string();
+
+    let mut args = vec!["-o".to_string(), output_template];
+
+    if options.restrict_filenames {
+        args.push("--restrict-filenames".to_string());
+    }
+    if let Some(length) = options.trim_filenames {
+        args.push("--trim-filenames".to_string());
+        args.push(length.to_string());
+    }
+    match options.on_existing.as_deref() {
+        Some("skip") => args.push("--no-overwrites".to_string()),
+        Some("overwrite") => args.push("--force-overwrites".to_string()),
+        _ => {}
+    }
+
+    // Collected so arguments for the same postprocessor end up in one flag
+    let mut postprocessor_args: Vec<(String, String)> = Vec::new();
+
+    // DownloadMode에 따라 인자 추가
+    match &options.mode {
+        DownloadMode::Video {
+            quality,
+            container,
+            video_codec,
+        } => {
+            args.push("-f".to_string());
+            args.push(video_format_selector(
+                options,
+                quality,
+                video_codec.as_deref(),
+            )?);
+
+            if !options.no_merge {
                 // 컨테이너 포맷 지정
-                args.push("--merge-output-format".to_string());
-                args.push(match container {
+                let merge_format = match container {
                     VideoContainer::Mp4 => "mp4",
                     VideoContainer::Mkv => "mkv",
                     VideoContainer::Webm => "webm",
-                }.to_string());
+                    VideoContainer::Mov => "mov",
+                    VideoContainer::Avi => "avi",
+                    VideoContainer::SmartMp4 => {
+                        resolved.smart_container.unwrap_or(SMART_CONTAINER_FALLBACK)
+                    }
+                };
+                args.push("--merge-output-format".to_string());
+                args.push(merge_format.to_string());
+
+                if merge_format == "mp4" && options.mp4_faststart {
+                    postprocessor_args
+                        .push(("ffmpeg".to_string(), "-movflags +faststart".to_string()));
+                }
+            }
+
+            // When the fallback kicks in, prefer the resolution closest to the cap
+            if let Some(max) = options.max_height.filter(|_| has_height_bounds(options)) {
+                args.push("-S".to_string());
+                args.push(format!("res:{}", max));
+            }
+        }
+        DownloadMode::Audio {
+            format,
+            audio_lang,
+            quality,
+            keep_video,
+        } => {
+            if let Some(id) = chosen_format_id(options) {
+                args.push("-f".to_string());
+                args.push(id.to_string());
+            } else if let Some(lang) = audio_lang {
+                args.push("-f".to_string());
+                args.push(format!("ba[language={}]/ba/b", lang));
             }
-            DownloadMode::Audio { format } => {
-                args.push("-x".to_string());
-                args.push("--audio-format".to_string());
-                args.push(match format {
+            args.push("-x".to_string());
+            args.push("--audio-format".to_string());
+            args.push(
+                match format {
                     AudioFormat::Mp3 => "mp3",
                     AudioFormat::M4a => "m4a",
                     AudioFormat::Aac => "aac",
                     AudioFormat::Flac => "flac",
                     AudioFormat::Wav => "wav",
-                }.to_string());
+                    AudioFormat::Opus => "opus",
+                    AudioFormat::Vorbis => "vorbis",
+                }
+                .to_string(),
+            );
+            if let Some(quality) = quality {
+                args.push("--audio-quality".to_string());
+                args.push(quality.clone());
             }
+            if *keep_video {
+                args.push("--keep-video".to_string());
+            }
+        }
+        DownloadMode::Thumbnail => {
+            args.push("--write-thumbnail".to_string());
+            args.push("--skip-download".to_string());
+            args.push("--convert-thumbnails".to_string());
+            args.push("jpg".to_string());
         }
+    }
+
+    let burn_lang = options.burn_subtitles.as_ref().filter(|l| !l.is_empty());
+    let wants_subs = options.embed_subs || options.write_subs_external;
 
-        if options.embed_subs {
+    if wants_subs || burn_lang.is_some() {
+        // --embed-subs alone fetches the subtitles and deletes them once
+        // embedded; --write-subs keeps them on disk
+        match (options.embed_subs, options.write_subs_external) {
+            (true, true) => {
+                args.push("--write-subs".to_string());
+                args.push("--embed-subs".to_string());
+                args.push("--convert-subs".to_string());
+                args.push("srt".to_string());
+            }
+            (true, false) => {
+                args.push("--embed-subs".to_string());
+            }
+            (false, true) => {
+                args.push("--write-subs".to_string());
+                args.push("--convert-subs".to_string());
+                args.push("srt".to_string());
+            }
+            (false, false) => {}
+        }
+        // Burning needs the subtitle file on disk
+        if burn_lang.is_some() && !options.write_subs_external {
             args.push("--write-subs".to_string());
-            args.push("--embed-subs".to_string());
         }
+        if resolved.write_auto_subs {
+            args.push("--write-auto-subs".to_string());
+        }
+
+        if let Some(format) = &options.sub_format {
+            args.push("--sub-format".to_string());
+            args.push(format.trim().to_string());
+        }
+
+        let mut langs = if wants_subs {
+            options.sub_langs.clone().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if wants_subs && langs.is_empty() {
+            langs.push("all".to_string());
+        }
+        if let Some(lang) = burn_lang {
+            if !langs.iter().any(|l| l == lang || l == "all") {
+                langs.push(lang.clone());
+            }
+        }
+        if !langs.is_empty() {
+            args.push("--sub-langs".to_string());
+            args.push(langs.join(","));
+        }
+    }
 
-        if let Some(items) = &options.playlist_items {
+    match options
+        .playlist_items
+        .as_ref()
+        .filter(|items| !items.is_empty())
+    {
+        Some(items) => {
             let items_str = items
                 .iter()
                 .map(|i| i.to_string())
@@ -315,120 +3306,268 @@ impl Downloader {
             args.push("--playlist-items".to_string());
             args.push(items_str);
         }
+        // Picked items download in the order given
+        None => match options.playlist_order.as_deref() {
+            Some("reverse") => args.push("--playlist-reverse".to_string()),
+            Some("random") => args.push("--playlist-random".to_string()),
+            _ => {}
+        },
+    }
 
-        // Add ffmpeg location if available
-        if self.manager.is_ffmpeg_installed() {
-            args.push("--ffmpeg-location".to_string());
-            args.push(self.manager.get_ffmpeg_path().to_string_lossy().to_string());
+    let mut cuts = false;
+    if let Some(categories) = &options.sponsorblock_remove {
+        if !categories.is_empty() {
+            args.push("--sponsorblock-remove".to_string());
+            args.push(categories.join(","));
+            cuts = true;
         }
+    }
 
-        args.push(options.url.clone());
-
-        // Emit starting status immediately
-        on_progress(DownloadProgress {
-            status: "starting".to_string(),
-            percentage: Some(0.0),
-            speed: None,
-            eta: None,
-            filename: None,
-            total_bytes: None,
-            downloaded_bytes: None,
-        });
-
-        let mut cmd = Command::new(self.manager.get_ytdlp_path());
-        cmd.args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    if options.live_from_start {
+        args.push("--live-from-start".to_string());
+    }
 
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(CREATE_NO_WINDOW);
+    if let Some(sections) = &options.download_sections {
+        args.push("--download-sections".to_string());
+        args.push(sections.trim().to_string());
+        cuts = true;
+    }
 
-        let mut child = cmd.spawn()?;
+    if cuts && options.force_keyframes_at_cuts {
+        args.push("--force-keyframes-at-cuts".to_string());
+    }
 
-        let stdout = child.stdout.take().unwrap();
-        let reader = BufReader::new(stdout);
-        let mut lines = reader.lines();
+    args.extend(connection_args(options)?);
 
-        let progress_regex = Regex::new(
-            r"\[download\]\s+(\d+\.?\d*)%\s+of\s+~?\s*([\d.]+\w+)(?:\s+at\s+([\d.]+\w+/s))?(?:\s+ETA\s+(\S+))?",
-        )
-        .unwrap();
+    if options.use_netrc {
+        args.push("--netrc".to_string());
+        if let Some(path) = &options.netrc_path {
+            args.push("--netrc-location".to_string());
+            args.push(path.clone());
+        }
+    }
 
-        while let Ok(Some(line)) = lines.next_line().await {
-            // Detect video info extraction phase
-            if line.starts_with("[youtube]") || line.starts_with("[info]") || line.contains("Extracting") {
-                on_progress(DownloadProgress {
-                    status: "extracting".to_string(),
-                    percentage: Some(0.0),
-                    speed: None,
-                    eta: None,
-                    filename: None,
-                    total_bytes: None,
-                    downloaded_bytes: None,
-                });
-                continue;
-            }
-            if let Some(caps) = progress_regex.captures(&line) {
-                let percentage = caps.get(1).and_then(|m| m.as_str().parse::<f64>().ok());
-                let speed = caps.get(3).map(|m| m.as_str().to_string());
-                let eta = caps.get(4).map(|m| m.as_str().to_string());
+    if let Some(filter) = build_match_filter(
+        options.match_filter.as_deref(),
+        options.min_duration,
+        options.max_duration,
+    ) {
+        args.push("--match-filter".to_string());
+        args.push(filter);
+    }
 
-                on_progress(DownloadProgress {
-                    status: "downloading".to_string(),
-                    percentage,
-                    speed,
-                    eta,
-                    filename: None,
-                    total_bytes: None,
-                    downloaded_bytes: None,
-                });
-            } else if line.contains("[download] Destination:") {
-                let filename = line.replace("[download] Destination:", "").trim().to_string();
-                on_progress(DownloadProgress {
-                    status: "starting".to_string(),
-                    percentage: Some(0.0),
-                    speed: None,
-                    eta: None,
-                    filename: Some(filename),
-                    total_bytes: None,
-                    downloaded_bytes: None,
-                });
-            } else if line.contains("[Merger]") || line.contains("[ExtractAudio]") {
-                on_progress(DownloadProgress {
-                    status: "processing".to_string(),
-                    percentage: Some(100.0),
-                    speed: None,
-                    eta: None,
-                    filename: None,
-                    total_bytes: None,
-                    downloaded_bytes: None,
-                });
+    postprocessor_args.extend(options.postprocessor_args.iter().flatten().cloned());
+    let mut merged_pp_args: Vec<(String, String)> = Vec::new();
+    for (name, pp_args) in postprocessor_args {
+        match merged_pp_args
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(&name))
+        {
+            Some((_, existing)) => {
+                existing.push(' ');
+                existing.push_str(&pp_args);
             }
+            None => merged_pp_args.push((name, pp_args)),
         }
+    }
+    for (name, pp_args) in merged_pp_args {
+        args.push("--postprocessor-args".to_string());
+        args.push(format!("{}:{}", name, pp_args));
+    }
 
-        let status = child.wait().await?;
+    if let Some(expr) = &options.retry_sleep {
+        args.push("--retry-sleep".to_string());
+        args.push(expr.trim().to_string());
+    }
 
-        if status.success() {
-            on_progress(DownloadProgress {
-                status: "completed".to_string(),
-                percentage: Some(100.0),
-                speed: None,
-                eta: None,
-                filename: None,
-                total_bytes: None,
-                downloaded_bytes: None,
-            });
-            Ok(options.output_dir.clone())
-        } else {
-            Err(DownloaderError::DownloadFailed(
-                "Download process failed".to_string(),
-            ))
+    if let Some(retries) = &options.fragment_retries {
+        args.push("--fragment-retries".to_string());
+        args.push(retries.clone());
+    }
+
+    if let Some(downloader) = &options.external_downloader {
+        args.push("--downloader".to_string());
+        args.push(downloader.clone());
+        args.push("--downloader-args".to_string());
+        args.push(format!("{}:{}", downloader, ARIA2C_ARGS));
+    }
+
+    if let Some(rate) = &options.rate_limit {
+        args.push("--limit-rate".to_string());
+        args.push(rate.trim().to_string());
+    }
+
+    // Add ffmpeg location if available
+    if manager.is_ffmpeg_installed() {
+        args.push("--ffmpeg-location".to_string());
+        args.push(manager.get_ffmpeg_path().to_string_lossy().to_string());
+    }
+
+    if options.embed_thumbnail {
+        args.push("--embed-thumbnail".to_string());
+        // WebP cover art isn't shown by many players and car stereos
+        args.push("--convert-thumbnails".to_string());
+        args.push("jpg".to_string());
+    }
+
+    if options.embed_metadata || options.embed_source_url {
+        args.push("--embed-metadata".to_string());
+    }
+    if options.embed_metadata {
+        args.push("--embed-chapters".to_string());
+    }
+    if options.embed_source_url {
+        args.push("--parse-metadata".to_string());
+        args.push("webpage_url:%(meta_comment)s".to_string());
+    }
+
+    let url = match options.channel_tab.as_deref() {
+        Some(tab) if !CHANNEL_TABS.contains(&tab) => {
+            return Err(DownloaderError::ExecutionError(format!(
+                "Unknown channel tab: {}",
+                tab
+            )));
+        }
+        Some(tab) => channel_tab_url(&options.url, tab).ok_or_else(|| {
+            DownloaderError::ExecutionError(
+                "A channel tab can only be chosen for a YouTube channel URL".to_string(),
+            )
+        })?,
+        None => options.url.clone(),
+    };
+
+    match &resolved.info_json_path {
+        Some(info_path) => {
+            args.push("--load-info-json".to_string());
+            args.push(info_path.clone());
         }
+        None => args.push(url),
     }
 
-    pub fn get_manager(&self) -> &YtDlpManager {
-        &self.manager
+    Ok(args)
+}
+
+/// `format_id`, when one was chosen.
+fn chosen_format_id(options: &DownloadOptions) -> Option<&str> {
+    options.format_id.as_deref().map(str::trim).filter(|f| !f.is_empty())
+}
+
+fn has_height_bounds(options: &DownloadOptions) -> bool {
+    chosen_format_id(options).is_none()
+        && (options.min_height.is_some() || options.max_height.is_some())
+}
+
+/// The video mode `-f` selector: `format_id` when chosen, otherwise one
+/// derived from the quality, height bounds and preferred codec.
+fn video_format_selector(
+    options: &DownloadOptions,
+    quality: &VideoQuality,
+    video_codec: Option<&str>,
+) -> Result<String, DownloaderError> {
+    if let Some(id) = chosen_format_id(options) {
+        return Ok(id.to_string());
+    }
+
+    let codec_filter = match video_codec {
+        Some(codec) => Some(video_codec_filter(codec).ok_or_else(|| {
+            DownloaderError::ExecutionError(format!("Unknown video codec: {}", codec))
+        })?),
+        None => None,
+    };
+
+    let selector = match (options.no_merge, has_height_bounds(options)) {
+        // 스트림을 분리된 파일로 유지
+        (true, true) => format!("bv*{},ba", height_filter(options.min_height, options.max_height)),
+        (true, false) => quality.to_separate_format_string(),
+        (false, true) => height_range_format(options.min_height, options.max_height),
+        (false, false) => quality.to_format_string(),
+    };
+    Ok(match codec_filter {
+        Some(filter) => prefer_video_codec(&selector, filter),
+        None => selector,
+    })
+}
+
+/// Cookie, proxy, header and geo-bypass arguments for `options`.
+fn connection_args(options: &DownloadOptions) -> Result<Vec<String>, DownloaderError> {
+    let mut args = cookie_args(
+        options.cookies_from_browser.as_deref(),
+        options.cookies_file.as_deref(),
+    )?;
+    args.extend(proxy_args(options.proxy.as_deref())?);
+    args.extend(header_args(
+        options.http_headers.as_deref(),
+        options.user_agent.as_deref(),
+    )?);
+    args.extend(geo_bypass_args(
+        options.geo_bypass,
+        options.geo_bypass_country.as_deref(),
+    )?);
+    Ok(args)
+}
+
+/// Checks a `--sub-format` value: formats like `srt`, optionally with `/` fallbacks.
+pub fn is_valid_sub_format(format: &str) -> bool {
+    Regex::new(r"^[A-Za-z0-9]+(/[A-Za-z0-9]+)*$").unwrap().is_match(format.trim())
+}
+
+/// Checks a `--download-sections` time range: `*HH:MM:SS-HH:MM:SS` with the
+/// start before the end.
+pub fn is_valid_download_sections(sections: &str) -> bool {
+    let re = Regex::new(r"^\*(\d{1,2}:\d{2}:\d{2})-(\d{1,2}:\d{2}:\d{2})$").unwrap();
+    let Some(caps) = re.captures(sections.trim()) else {
+        return false;
+    };
+    match (parse_timestamp(&caps[1]), parse_timestamp(&caps[2])) {
+        (Some(start), Some(end)) => start < end,
+        _ => false,
+    }
+}
+
+fn has_extension(path: &str, extensions: &[&str]) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| extensions.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Finds the `<stem>.<lang>.<ext>` subtitle yt-dlp writes next to `video`.
+fn find_subtitle_file(video: &std::path::Path, lang: &str) -> Option<PathBuf> {
+    let stem = video.file_stem()?.to_string_lossy();
+    SUBTITLE_EXTENSIONS
+        .iter()
+        .map(|ext| video.with_file_name(format!("{}.{}.{}", stem, lang, ext)))
+        .find(|path| path.is_file())
+}
+
+/// Parses `SS`, `MM:SS` or `HH:MM:SS` (seconds may have a fraction) into seconds.
+pub fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let parts: Vec<&str> = timestamp.trim().split(':').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+
+    let (seconds_part, leading) = parts.split_last()?;
+    let seconds: f64 = seconds_part.parse().ok().filter(|s: &f64| *s >= 0.0)?;
+    if !leading.is_empty() && seconds >= 60.0 {
+        return None;
+    }
+
+    let mut total = seconds;
+    for (i, part) in leading.iter().rev().enumerate() {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let value: u64 = part.parse().ok()?;
+        // Minutes are capped when hours are present
+        if i == 0 && leading.len() == 2 && value >= 60 {
+            return None;
+        }
+        total += value as f64 * 60f64.powi(i as i32 + 1);
     }
+
+    Some(total)
 }
 
 impl Default for Downloader {
@@ -436,3 +3575,221 @@ impl Default for Downloader {
         Self::new().expect("Failed to create Downloader")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> YtDlpManager {
+        YtDlpManager::with_bin_dir(std::env::temp_dir().join("yt-dlp-gui-test-bin"))
+    }
+
+    fn resolved() -> ResolvedInputs {
+        ResolvedInputs {
+            smart_container: None,
+            write_auto_subs: false,
+            info_json_path: None,
+        }
+    }
+
+    fn options(mode: DownloadMode) -> DownloadOptions {
+        DownloadOptions {
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            output_dir: "out".to_string(),
+            mode,
+            ..Default::default()
+        }
+    }
+
+    fn args_for(options: &DownloadOptions) -> Vec<String> {
+        build_download_args(options, &manager(), &resolved()).unwrap()
+    }
+
+    fn has_pair(args: &[String], flag: &str, value: &str) -> bool {
+        args.windows(2).any(|w| w[0] == flag && w[1] == value)
+    }
+
+    #[test]
+    fn audio_mode_extracts_audio() {
+        let args = args_for(&options(DownloadMode::Audio {
+            format: AudioFormat::Mp3,
+            audio_lang: None,
+            quality: None,
+            keep_video: false,
+        }));
+        assert!(args.iter().any(|a| a == "-x"));
+        assert!(has_pair(&args, "--audio-format", "mp3"));
+        assert_eq!(args.last().unwrap(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn video_mode_sets_merge_format() {
+        let args = args_for(&options(DownloadMode::Video {
+            quality: VideoQuality::Best,
+            container: VideoContainer::Mkv,
+            video_codec: None,
+        }));
+        assert!(has_pair(&args, "--merge-output-format", "mkv"));
+        assert!(!args.iter().any(|a| a == "-x"));
+    }
+
+    #[test]
+    fn embed_subs_fetches_and_embeds_subtitles() {
+        let mut opts = options(DownloadMode::default());
+        opts.embed_subs = true;
+        opts.sub_langs = Some(vec!["en".to_string(), "ko".to_string()]);
+        let args = args_for(&opts);
+        assert!(args.iter().any(|a| a == "--embed-subs"));
+        assert!(has_pair(&args, "--sub-langs", "en,ko"));
+
+        opts.embed_subs = false;
+        let args = args_for(&opts);
+        assert!(!args.iter().any(|a| a == "--embed-subs" || a == "--write-subs"));
+    }
+
+    // `--flat-playlist` line for the only entry of a playlist
+    const SINGLE_ENTRY_PLAYLIST: &str = r#"{"_type": "url", "ie_key": "Youtube", "id": "dQw4w9WgXcQ", "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ", "title": "Never Gonna Give You Up", "duration": 212.0, "playlist_id": "PLsingle", "playlist_title": "One Song", "playlist_uploader": "Someone", "playlist_count": 1, "playlist_index": 1}"#;
+
+    const SINGLE_VIDEO: &str = r#"{"id": "dQw4w9WgXcQ", "title": "Never Gonna Give You Up", "duration": 212, "uploader": "Rick Astley", "extractor_key": "Youtube", "view_count": 1500000000, "upload_date": "20091025", "formats": [{"format_id": "140", "acodec": "mp4a.40.2", "vcodec": "none", "language": "en"}], "thumbnails": [{"url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg", "width": 480, "height": 360}]}"#;
+
+    #[test]
+    fn single_entry_playlist_is_a_playlist() {
+        let info = parse_video_info(&[SINGLE_ENTRY_PLAYLIST], None, 0).unwrap();
+        assert!(info.is_playlist);
+        assert_eq!(info.id, "PLsingle");
+        assert_eq!(info.title, "One Song");
+        assert_eq!(info.uploader.as_deref(), Some("Someone"));
+        assert_eq!(info.playlist_count, Some(1));
+
+        let entries = info.entries.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "dQw4w9WgXcQ");
+        assert_eq!(entries[0].duration, Some(212.0));
+    }
+
+    #[test]
+    fn single_video_is_not_a_playlist() {
+        let info = parse_video_info(&[SINGLE_VIDEO], Some("Youtube".to_string()), 42).unwrap();
+        assert!(!info.is_playlist);
+        assert!(info.entries.is_none());
+        assert_eq!(info.id, "dQw4w9WgXcQ");
+        assert_eq!(info.uploader.as_deref(), Some("Rick Astley"));
+        assert_eq!(info.duration, Some(212.0));
+        assert_eq!(info.view_count, Some(1_500_000_000));
+        assert_eq!(info.audio_languages, Some(vec!["en".to_string()]));
+        assert_eq!(info.thumbnails.len(), 1);
+        assert_eq!(info.fetch_ms, 42);
+    }
+
+    #[test]
+    fn playlist_items_are_joined() {
+        let mut opts = options(DownloadMode::default());
+        opts.playlist_items = Some(vec![1, 3, 7]);
+        opts.playlist_order = Some("reverse".to_string());
+        let args = args_for(&opts);
+        assert!(has_pair(&args, "--playlist-items", "1,3,7"));
+        // An explicit selection wins over the order
+        assert!(!args.iter().any(|a| a == "--playlist-reverse"));
+    }
+
+    #[test]
+    fn smart_quality_format_strings() {
+        let quality = VideoQuality::Smart { max_height: 1080 };
+        assert_eq!(
+            quality.to_format_string(),
+            "bv*[height<=1080]+ba/b[height<=1080]/bv*+ba/b"
+        );
+        assert_eq!(quality.to_separate_format_string(), "bv*[height<=1080],ba");
+    }
+
+    #[test]
+    fn height_range_format_bounds() {
+        assert_eq!(
+            height_range_format(Some(720), None),
+            "bv*[height>=720]+ba/b[height>=720]/bv*+ba/b"
+        );
+        assert_eq!(
+            height_range_format(None, Some(1080)),
+            "bv*[height<=1080]+ba/b[height<=1080]/bv*+ba/b"
+        );
+        assert_eq!(
+            height_range_format(Some(720), Some(1080)),
+            "bv*[height>=720][height<=1080]+ba/b[height>=720][height<=1080]/bv*+ba/b"
+        );
+        assert_eq!(height_range_format(None, None), "bv*+ba/b/bv*+ba/b");
+        assert_eq!(height_filter(None, None), "");
+    }
+
+    #[test]
+    fn auto_captions_needed_only_without_manual_subs() {
+        let langs = vec!["en".to_string()];
+
+        let auto_only: serde_json::Value = serde_json::from_str(
+            r#"{"id": "a", "subtitles": {}, "automatic_captions": {"en": [{"ext": "vtt"}]}}"#,
+        )
+        .unwrap();
+        assert!(needs_auto_captions(Some(&auto_only), &langs));
+
+        let manual: serde_json::Value = serde_json::from_str(
+            r#"{"id": "b", "subtitles": {"en": [{"ext": "vtt"}]}, "automatic_captions": {"en": [{"ext": "vtt"}]}}"#,
+        )
+        .unwrap();
+        assert!(!needs_auto_captions(Some(&manual), &langs));
+    }
+
+    #[test]
+    fn progress_line_carries_title_with_slashes() {
+        let progress =
+            parse_progress_line("__progress__ 1024/4096/NA/512.0/6/False/AC/DC - Live 1991")
+                .unwrap();
+        assert_eq!(progress.title.as_deref(), Some("AC/DC - Live 1991"));
+        assert_eq!(progress.percentage, Some(25.0));
+
+        let progress = parse_progress_line("__progress__ 1024/NA/NA/NA/NA/NA/NA").unwrap();
+        assert!(progress.title.is_none());
+    }
+
+    #[test]
+    fn live_progress_without_total_is_recording() {
+        let progress = parse_progress_line("__progress__ 1024/NA/NA/512.0/NA/True/Stream").unwrap();
+        assert_eq!(progress.status, "recording");
+
+        let progress = parse_progress_line("__progress__ 1024/4096/NA/512.0/6/True/Stream").unwrap();
+        assert_eq!(progress.status, "downloading");
+        let progress = parse_progress_line("__progress__ 1024/NA/NA/512.0/NA/False/Clip").unwrap();
+        assert_eq!(progress.status, "downloading");
+    }
+
+
+    #[test]
+    fn forbidden_is_not_a_network_error() {
+        let error = classify_stderr(
+            "[download] Got error: HTTP Error 403: Forbidden\nERROR: unable to download video data: HTTP Error 403: Forbidden",
+        );
+        assert!(!matches!(error, Some(DownloaderError::NetworkError(_))));
+
+        let error = classify_stderr(
+            "ERROR: unable to download video data: HTTP Error 503: Service Unavailable",
+        );
+        assert!(matches!(error, Some(DownloaderError::NetworkError(_))));
+    }
+
+    #[test]
+    fn warnings_do_not_decide_the_category() {
+        let error = classify_stderr(
+            "WARNING: Unable to download video thumbnail: HTTP Error 404: Not Found\nERROR: [Errno 104] Connection reset by peer",
+        )
+        .unwrap();
+        assert!(matches!(error, DownloaderError::NetworkError(_)));
+        assert!(is_transient_error(&error));
+    }
+
+    #[test]
+    fn spawn_failures_are_not_transient() {
+        let error = DownloaderError::IoError(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!is_transient_error(&error));
+        let error =
+            DownloaderError::IoError(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(is_transient_error(&error));
+    }
+}
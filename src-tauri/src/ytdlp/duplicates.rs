@@ -0,0 +1,175 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+// Only the head of each file is hashed in the first pass; full hashes are
+// computed only for files that still collide after that.
+const PARTIAL_HASH_BYTES: usize = 64 * 1024;
+
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "webm", "mov", "avi", "m4a", "mp3", "aac", "flac", "wav", "opus", "ogg",
+];
+
+#[derive(Error, Debug)]
+pub enum DuplicateError {
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Scan cancelled")]
+    Cancelled,
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateScanProgress {
+    pub hashed: usize,
+    pub total: usize,
+    pub current: Option<String>,
+}
+
+/// Groups identical media files under `dir`. Blocking; run on a blocking thread.
+pub fn find_duplicates<F>(
+    dir: &Path,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<Vec<DuplicateGroup>, DuplicateError>
+where
+    F: Fn(DuplicateScanProgress),
+{
+    if !dir.is_dir() {
+        return Err(DuplicateError::NotADirectory(
+            dir.to_string_lossy().to_string(),
+        ));
+    }
+
+    // Group by size first; files with a unique size can't have duplicates
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for path in collect_media_files(dir, cancel)? {
+        let size = std::fs::metadata(&path)?.len();
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+    by_size.retain(|_, paths| paths.len() > 1);
+
+    let total = by_size.values().map(|paths| paths.len()).sum();
+    let mut hashed = 0;
+    let mut groups = Vec::new();
+
+    for (size, paths) in by_size {
+        let mut by_partial: HashMap<[u8; 32], Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(DuplicateError::Cancelled);
+            }
+
+            hashed += 1;
+            on_progress(DuplicateScanProgress {
+                hashed,
+                total,
+                current: Some(path.to_string_lossy().to_string()),
+            });
+
+            by_partial
+                .entry(hash_file(&path, Some(PARTIAL_HASH_BYTES))?)
+                .or_default()
+                .push(path);
+        }
+
+        for candidates in by_partial.into_values().filter(|p| p.len() > 1) {
+            // Files no larger than the partial window are already fully hashed
+            if size as usize <= PARTIAL_HASH_BYTES {
+                groups.push(to_group(size, candidates));
+                continue;
+            }
+
+            let mut by_full: HashMap<[u8; 32], Vec<PathBuf>> = HashMap::new();
+            for path in candidates {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(DuplicateError::Cancelled);
+                }
+                by_full.entry(hash_file(&path, None)?).or_default().push(path);
+            }
+
+            groups.extend(
+                by_full
+                    .into_values()
+                    .filter(|p| p.len() > 1)
+                    .map(|p| to_group(size, p)),
+            );
+        }
+    }
+
+    groups.sort_by_key(|g| std::cmp::Reverse(g.size));
+    Ok(groups)
+}
+
+fn collect_media_files(dir: &Path, cancel: &AtomicBool) -> Result<Vec<PathBuf>, DuplicateError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(DuplicateError::Cancelled);
+        }
+
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_media_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+pub(crate) fn is_media_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn hash_file(path: &Path, limit: Option<usize>) -> Result<[u8; 32], DuplicateError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; PARTIAL_HASH_BYTES];
+    let mut remaining = limit.unwrap_or(usize::MAX);
+
+    while remaining > 0 {
+        let want = remaining.min(buf.len());
+        let read = file.read(&mut buf[..want])?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        remaining -= read;
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+fn to_group(size: u64, mut paths: Vec<PathBuf>) -> DuplicateGroup {
+    paths.sort();
+    DuplicateGroup {
+        size,
+        paths: paths
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+    }
+}
@@ -0,0 +1,182 @@
+use crate::ytdlp::disk::atomic_write;
+use crate::ytdlp::manager::YtDlpManager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+// Serializes read-modify-write cycles when several downloads finish at once
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("Manager error: {0}")]
+    Manager(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: Option<String>,
+    pub output_path: Option<String>,
+    /// `"video"`, `"audio"` or `"thumbnail"`.
+    pub mode: String,
+    pub timestamp: String,
+    pub size: Option<u64>,
+}
+
+fn path() -> Result<PathBuf, HistoryError> {
+    let dir = YtDlpManager::get_app_data_dir()
+        .map_err(|e| HistoryError::Manager(e.to_string()))?;
+    Ok(dir.join("history.json"))
+}
+
+fn read(path: &Path) -> Result<Vec<HistoryEntry>, HistoryError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write(path: &Path, entries: &[HistoryEntry]) -> Result<(), HistoryError> {
+    atomic_write(path, serde_json::to_string_pretty(entries)?)?;
+    Ok(())
+}
+
+/// All recorded downloads, oldest first.
+pub fn load() -> Result<Vec<HistoryEntry>, HistoryError> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    read(&path()?)
+}
+
+pub fn append(entry: HistoryEntry) -> Result<(), HistoryError> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let path = path()?;
+    let mut entries = read(&path)?;
+    entries.push(entry);
+    write(&path, &entries)
+}
+
+pub fn clear() -> Result<(), HistoryError> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    write(&path()?, &[])
+}
+
+// Query parameters that don't change which video a URL points at
+const IGNORED_QUERY_PARAMS: &[&str] = &["si", "feature", "t", "pp", "ab_channel"];
+
+/// Reduces `url` to a canonical form so the same video compares equal however
+/// it was shared: `youtu.be`/`shorts` links become `watch?v=`, `www.`/`m.` and
+/// tracking parameters are dropped.
+pub fn normalize_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url.trim()) else {
+        return url.trim().to_string();
+    };
+
+    let host = parsed.host_str().unwrap_or("").to_ascii_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host)
+        .to_string();
+
+    let video_id = match host.as_str() {
+        "youtu.be" => parsed.path_segments().and_then(|mut s| s.next()),
+        "youtube.com" => parsed
+            .path()
+            .strip_prefix("/shorts/")
+            .map(|rest| rest.split('/').next().unwrap_or(rest)),
+        _ => None,
+    }
+    .filter(|id| !id.is_empty())
+    .map(|id| id.to_string());
+
+    let mut query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !IGNORED_QUERY_PARAMS.contains(&k.as_ref()) && !k.starts_with("utm_"))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+
+    if let Some(id) = video_id {
+        query.retain(|(k, _)| k != "v");
+        query.insert(0, ("v".to_string(), id));
+        return format!("https://youtube.com/watch?{}", encode_query(&query));
+    }
+
+    // The fragment is dropped along with the rest of the original formatting
+    let path = parsed.path().trim_end_matches('/');
+    if query.is_empty() {
+        format!("{}://{}{}", parsed.scheme(), host, path)
+    } else {
+        format!("{}://{}{}?{}", parsed.scheme(), host, path, encode_query(&query))
+    }
+}
+
+fn encode_query(pairs: &[(String, String)]) -> String {
+    reqwest::Url::parse_with_params("http://x/", pairs)
+        .ok()
+        .and_then(|u| u.query().map(|q| q.to_string()))
+        .unwrap_or_default()
+}
+
+/// Whether the same video was downloaded before, whether or not the file is still there.
+pub fn contains(url: &str) -> Result<bool, HistoryError> {
+    let normalized = normalize_url(url);
+    Ok(load()?.iter().any(|entry| normalize_url(&entry.url) == normalized))
+}
+
+/// Earlier downloads of the same video whose output file is still on disk,
+/// newest first. Downloading again would likely overwrite or duplicate them.
+pub fn find_existing(url: &str) -> Result<Vec<HistoryEntry>, HistoryError> {
+    let normalized = normalize_url(url);
+    let mut matches: Vec<HistoryEntry> = load()?
+        .into_iter()
+        .filter(|entry| normalize_url(&entry.url) == normalized)
+        .filter(|entry| {
+            entry
+                .output_path
+                .as_ref()
+                .is_some_and(|p| Path::new(p).exists())
+        })
+        .collect();
+    matches.reverse();
+    Ok(matches)
+}
+
+/// Writes the history as CSV to `dest`, returning the number of rows.
+pub fn export_csv(dest: &Path) -> Result<usize, HistoryError> {
+    let entries = load()?;
+
+    let mut csv = String::from("title,url,date,mode,output_path,size\r\n");
+    for entry in &entries {
+        let size = entry.size.map(|s| s.to_string()).unwrap_or_default();
+        let fields = [
+            entry.title.as_deref().unwrap_or(""),
+            &entry.url,
+            &entry.timestamp,
+            &entry.mode,
+            entry.output_path.as_deref().unwrap_or(""),
+            &size,
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    std::fs::write(dest, csv)?;
+    Ok(entries.len())
+}
+
+// RFC 4180: quote fields containing separators, quotes or line breaks, doubling inner quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
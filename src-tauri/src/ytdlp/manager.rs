@@ -1,6 +1,10 @@
 use directories::ProjectDirs;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 use thiserror::Error;
 
 #[cfg(target_os = "windows")]
@@ -9,6 +13,19 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+// Flags listed in `yt-dlp --help`, keyed by binary path and modification time so
+// an update (which rewrites the binary) invalidates the entry.
+type HelpCacheKey = (PathBuf, Option<SystemTime>);
+static HELP_CACHE: OnceLock<Mutex<HashMap<HelpCacheKey, Arc<HashSet<String>>>>> = OnceLock::new();
+
+// Result of the one-time PATH lookup for yt-dlp, shared by every manager since
+// commands create their own and the lookup runs `yt-dlp --version`
+static SYSTEM_YTDLP: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+// Tag of the versioned binary `get_ytdlp_path` resolves to; `None` means the
+// default `yt-dlp` binary.
+static ACTIVE_VERSION: Mutex<Option<String>> = Mutex::new(None);
+
 #[derive(Error, Debug)]
 pub enum ManagerError {
     #[error("Failed to get app data directory")]
@@ -17,10 +34,41 @@ pub enum ManagerError {
     BinaryNotFound,
     #[error("Failed to execute yt-dlp: {0}")]
     ExecutionError(String),
+    #[error("yt-dlp was blocked from running ({0}). Your antivirus may have quarantined it; add an exclusion for {1} and reinstall yt-dlp.")]
+    Blocked(String, String),
+    #[error("Invalid yt-dlp version tag: {0}")]
+    InvalidVersion(String),
+    #[error("yt-dlp {0} is not installed")]
+    VersionNotInstalled(String),
+    #[error("yt-dlp {0} is the active version; switch to another version first")]
+    VersionInUse(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+/// A versioned yt-dlp binary kept alongside the default one.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InstalledVersion {
+    pub tag: String,
+    pub path: String,
+    pub active: bool,
+}
+
+/// yt-dlp release tags look like `2024.03.10`, optionally with a build suffix.
+pub fn is_valid_version_tag(tag: &str) -> bool {
+    Regex::new(r"^\d{4}\.\d{2}\.\d{2}(\.\d+)?$").unwrap().is_match(tag)
+}
+
+/// How the user can get ffmpeg on this platform.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FfmpegInstallGuidance {
+    /// The app can download ffmpeg itself (Windows, macOS, Linux x86_64/aarch64).
+    pub auto_install: bool,
+    pub distro: Option<String>,
+    pub command: Option<String>,
+    pub message: String,
+}
+
 #[derive(Clone)]
 pub struct YtDlpManager {
     bin_dir: PathBuf,
@@ -39,11 +87,56 @@ impl YtDlpManager {
         Ok(Self { bin_dir })
     }
 
+    /// A manager over `bin_dir` that creates nothing on disk.
+    #[cfg(test)]
+    pub(crate) fn with_bin_dir(bin_dir: PathBuf) -> Self {
+        Self { bin_dir }
+    }
+
     pub fn get_bin_dir(&self) -> &PathBuf {
         &self.bin_dir
     }
 
+    /// The active versioned binary if one is selected and present, else the
+    /// managed default one, else a yt-dlp found on PATH. Returns the default
+    /// path when none exists.
     pub fn get_ytdlp_path(&self) -> PathBuf {
+        if let Some(tag) = Self::active_version() {
+            let path = self.get_versioned_ytdlp_path(&tag);
+            if path.exists() {
+                return path;
+            }
+        }
+
+        let default_path = self.get_default_ytdlp_path();
+        if default_path.exists() {
+            return default_path;
+        }
+        SYSTEM_YTDLP
+            .get_or_init(Self::find_system_ytdlp)
+            .clone()
+            .unwrap_or(default_path)
+    }
+
+    /// A working yt-dlp on PATH, e.g. one installed by a package manager.
+    pub fn find_system_ytdlp() -> Option<PathBuf> {
+        find_on_path("yt-dlp")
+    }
+
+    /// Whether aria2c is on PATH, for `external_downloader`.
+    pub fn is_aria2c_installed(&self) -> bool {
+        find_on_path("aria2c").is_some()
+    }
+
+    /// Whether `get_ytdlp_path` resolves to a yt-dlp on PATH rather than a managed one.
+    pub fn is_using_system_ytdlp(&self) -> bool {
+        SYSTEM_YTDLP
+            .get()
+            .and_then(|p| p.as_ref())
+            .is_some_and(|p| *p == self.get_ytdlp_path())
+    }
+
+    pub fn get_default_ytdlp_path(&self) -> PathBuf {
         #[cfg(target_os = "windows")]
         {
             self.bin_dir.join("yt-dlp.exe")
@@ -54,6 +147,80 @@ impl YtDlpManager {
         }
     }
 
+    pub fn get_versioned_ytdlp_path(&self, tag: &str) -> PathBuf {
+        self.bin_dir
+            .join(format!("yt-dlp-{}{}", tag, std::env::consts::EXE_SUFFIX))
+    }
+
+    pub fn active_version() -> Option<String> {
+        ACTIVE_VERSION.lock().unwrap().clone()
+    }
+
+    pub fn set_active_version(tag: Option<String>) {
+        *ACTIVE_VERSION.lock().unwrap() = tag;
+    }
+
+    pub fn list_installed_versions(&self) -> Result<Vec<InstalledVersion>, ManagerError> {
+        let active = Self::active_version();
+        let mut versions = Vec::new();
+
+        for entry in std::fs::read_dir(&self.bin_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(tag) = name
+                .strip_suffix(std::env::consts::EXE_SUFFIX)
+                .and_then(|n| n.strip_prefix("yt-dlp-"))
+            else {
+                continue;
+            };
+            if !is_valid_version_tag(tag) {
+                continue;
+            }
+
+            versions.push(InstalledVersion {
+                tag: tag.to_string(),
+                path: path.to_string_lossy().to_string(),
+                active: active.as_deref() == Some(tag),
+            });
+        }
+
+        // Newest first; tags sort chronologically as strings
+        versions.sort_by(|a, b| b.tag.cmp(&a.tag));
+        Ok(versions)
+    }
+
+    /// Makes `tag` the binary used for everything, or the default binary for `None`.
+    pub fn switch_version(&self, tag: Option<&str>) -> Result<(), ManagerError> {
+        if let Some(tag) = tag {
+            if !is_valid_version_tag(tag) {
+                return Err(ManagerError::InvalidVersion(tag.to_string()));
+            }
+            if !self.get_versioned_ytdlp_path(tag).exists() {
+                return Err(ManagerError::VersionNotInstalled(tag.to_string()));
+            }
+        }
+        Self::set_active_version(tag.map(String::from));
+        Ok(())
+    }
+
+    pub fn remove_version(&self, tag: &str) -> Result<(), ManagerError> {
+        if !is_valid_version_tag(tag) {
+            return Err(ManagerError::InvalidVersion(tag.to_string()));
+        }
+        if Self::active_version().as_deref() == Some(tag) {
+            return Err(ManagerError::VersionInUse(tag.to_string()));
+        }
+
+        let path = self.get_versioned_ytdlp_path(tag);
+        if !path.exists() {
+            return Err(ManagerError::VersionNotInstalled(tag.to_string()));
+        }
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
     pub fn get_ffmpeg_path(&self) -> PathBuf {
         #[cfg(target_os = "windows")]
         {
@@ -84,7 +251,7 @@ impl YtDlpManager {
         #[cfg(target_os = "windows")]
         cmd.creation_flags(CREATE_NO_WINDOW);
 
-        let output = cmd.output()?;
+        let output = cmd.output().map_err(|e| self.classify_exec_error(e))?;
 
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
@@ -95,6 +262,117 @@ impl YtDlpManager {
         }
     }
 
+    /// Runs the installed binary to confirm it actually executes, not just that it exists.
+    pub fn verify_ytdlp(&self) -> Result<String, ManagerError> {
+        // Missing binaries and blocked execution are both classified by get_ytdlp_version
+        self.get_ytdlp_version()
+    }
+
+    /// Whether the installed yt-dlp lists `flag` in its `--help` output.
+    pub fn supports_option(&self, flag: &str) -> Result<bool, ManagerError> {
+        let flag = if flag.starts_with('-') {
+            flag.to_string()
+        } else {
+            format!("--{}", flag)
+        };
+        Ok(self.supported_flags()?.contains(&flag))
+    }
+
+    /// Fits `args` to the installed yt-dlp with `adapt_args`, returning the
+    /// adapted arguments and the flags that had to be dropped. Leaves `args`
+    /// untouched if the help output can't be read, so callers never block on it.
+    pub fn adapt_to_installed(&self, args: Vec<String>) -> (Vec<String>, Vec<String>) {
+        match self.supported_flags() {
+            Ok(supported) => adapt_args(args, &supported),
+            Err(_) => (args, Vec::new()),
+        }
+    }
+
+    fn supported_flags(&self) -> Result<Arc<HashSet<String>>, ManagerError> {
+        if !self.is_ytdlp_installed() {
+            return Err(ManagerError::BinaryNotFound);
+        }
+
+        let path = self.get_ytdlp_path();
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let key = (path.clone(), modified);
+
+        let cache = HELP_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some(flags) = cache.lock().unwrap().get(&key) {
+            return Ok(flags.clone());
+        }
+
+        let mut cmd = Command::new(&path);
+        cmd.arg("--help");
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let output = cmd.output().map_err(|e| self.classify_exec_error(e))?;
+        if !output.status.success() {
+            return Err(ManagerError::ExecutionError(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        let flags = Arc::new(parse_help_flags(&String::from_utf8_lossy(&output.stdout)));
+
+        cache.lock().unwrap().insert(key, flags.clone());
+        Ok(flags)
+    }
+
+    fn classify_exec_error(&self, err: std::io::Error) -> ManagerError {
+        // ERROR_ACCESS_DENIED, ERROR_VIRUS_INFECTED, ERROR_VIRUS_DELETED
+        #[cfg(target_os = "windows")]
+        let blocked_by_os = matches!(err.raw_os_error(), Some(5) | Some(225) | Some(226));
+        #[cfg(not(target_os = "windows"))]
+        let blocked_by_os = false;
+
+        if blocked_by_os || err.kind() == std::io::ErrorKind::PermissionDenied {
+            ManagerError::Blocked(err.to_string(), self.bin_dir.to_string_lossy().to_string())
+        } else {
+            ManagerError::IoError(err)
+        }
+    }
+
+    /// Makes sure the bin dir is accessible and the managed binaries are executable.
+    /// Returns a description of every change made.
+    pub fn fix_permissions(&self) -> Result<Vec<String>, ManagerError> {
+        let mut changes = Vec::new();
+
+        if !self.bin_dir.exists() {
+            std::fs::create_dir_all(&self.bin_dir)?;
+            changes.push(format!("Created {}", self.bin_dir.display()));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mut perms = std::fs::metadata(&self.bin_dir)?.permissions();
+            if perms.mode() & 0o700 != 0o700 {
+                perms.set_mode(perms.mode() | 0o755);
+                std::fs::set_permissions(&self.bin_dir, perms)?;
+                changes.push(format!("Made {} accessible", self.bin_dir.display()));
+            }
+
+            for binary in [self.get_ytdlp_path(), self.get_ffmpeg_path()] {
+                if !binary.exists() {
+                    continue;
+                }
+
+                let mut perms = std::fs::metadata(&binary)?.permissions();
+                if perms.mode() & 0o111 != 0o111 {
+                    perms.set_mode(0o755);
+                    std::fs::set_permissions(&binary, perms)?;
+                    changes.push(format!("Made {} executable", binary.display()));
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
     pub fn get_download_url() -> (&'static str, &'static str) {
         #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
         {
@@ -147,17 +425,67 @@ impl YtDlpManager {
         {
             Some("https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip")
         }
-        #[cfg(target_os = "linux")]
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
         {
-            // On Linux, ffmpeg should be installed via package manager
-            None
+            Some("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-amd64-static.tar.xz")
         }
-        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+        #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+        {
+            Some("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-arm64-static.tar.xz")
+        }
+        // Other Linux architectures should install ffmpeg via the package manager
+        #[cfg(not(any(
+            target_os = "macos",
+            target_os = "windows",
+            all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))
+        )))]
         {
             None
         }
     }
 
+    /// On Linux the package-manager command is offered too, even where the app
+    /// can install a static build itself.
+    pub fn get_ffmpeg_install_guidance() -> FfmpegInstallGuidance {
+        let auto_install = Self::get_ffmpeg_download_url().is_some();
+        if auto_install && !cfg!(target_os = "linux") {
+            return FfmpegInstallGuidance {
+                auto_install: true,
+                distro: None,
+                command: None,
+                message: "ffmpeg can be installed automatically by the app.".to_string(),
+            };
+        }
+
+        let os_release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+        let distro = parse_os_release_field(&os_release, "ID");
+        let id_like = parse_os_release_field(&os_release, "ID_LIKE").unwrap_or_default();
+
+        // Check ID first, then each ID_LIKE parent (e.g. "ubuntu debian")
+        let command = distro
+            .iter()
+            .map(String::as_str)
+            .chain(id_like.split_whitespace())
+            .find_map(package_manager_command);
+
+        let message = match (auto_install, command) {
+            (true, Some(cmd)) => format!(
+                "ffmpeg can be installed automatically by the app, or with your package manager: {}",
+                cmd
+            ),
+            (true, None) => "ffmpeg can be installed automatically by the app.".to_string(),
+            (false, Some(cmd)) => format!("Install ffmpeg with your package manager: {}", cmd),
+            (false, None) => "Install ffmpeg using your distribution's package manager.".to_string(),
+        };
+
+        FfmpegInstallGuidance {
+            auto_install,
+            distro,
+            command: command.map(String::from),
+            message,
+        }
+    }
+
     pub fn get_app_data_dir() -> Result<PathBuf, ManagerError> {
         let project_dirs = ProjectDirs::from("com", "gyuseok", "yt-dlp-gui")
             .ok_or(ManagerError::NoAppDataDir)?;
@@ -171,8 +499,217 @@ impl YtDlpManager {
     }
 }
 
+/// Long options listed in yt-dlp's `--help` output.
+fn parse_help_flags(help: &str) -> HashSet<String> {
+    let flag_regex = Regex::new(r"--[a-z0-9][a-z0-9-]*").unwrap();
+    flag_regex
+        .find_iter(help)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+// Deprecated aliases that newer yt-dlp still accepts but no longer lists in
+// `--help`, with the number of values they take and their replacement
+const FLAG_ALIASES: &[(&str, usize, &[&str])] = &[
+    ("--geo-bypass", 0, &["--xff", "default"]),
+    ("--no-geo-bypass", 0, &["--xff", "never"]),
+    ("--geo-bypass-country", 1, &["--xff"]),
+    ("--playlist-reverse", 0, &["--playlist-items", "::-1"]),
+];
+
+/// Rewrites long options in `args` that aren't in `supported`: deprecated
+/// aliases become their replacement when that is supported, and anything else
+/// is dropped together with its value. Returns the adapted arguments and the
+/// dropped flags.
+///
+/// Arity of an unknown flag is guessed: the next argument is its value unless
+/// it is another option or the last argument, which is always the URL or an
+/// info JSON path.
+fn adapt_args(args: Vec<String>, supported: &HashSet<String>) -> (Vec<String>, Vec<String>) {
+    let mut adapted = Vec::with_capacity(args.len());
+    let mut dropped = Vec::new();
+    let mut iter = args.into_iter().peekable();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        if !arg.starts_with("--") || supported.contains(&flag) {
+            adapted.push(arg);
+            continue;
+        }
+
+        let alias = FLAG_ALIASES.iter().find(|(name, _, _)| *name == flag);
+        let takes_value = match alias {
+            Some((_, arity, _)) => *arity > 0,
+            None => iter.len() > 1 && iter.peek().is_some_and(|next| !next.starts_with('-')),
+        };
+        let value = match inline_value {
+            Some(value) => Some(value),
+            None if takes_value => iter.next(),
+            None => None,
+        };
+
+        match alias {
+            Some((_, _, replacement)) if supported.contains(replacement[0]) => {
+                adapted.extend(replacement.iter().map(|s| s.to_string()));
+                adapted.extend(value);
+            }
+            _ => dropped.push(flag),
+        }
+    }
+
+    (adapted, dropped)
+}
+
+/// First `name` executable on PATH that runs `--version` successfully.
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(&name))
+        .filter(|path| path.is_file())
+        .find(|path| {
+            let mut cmd = Command::new(path);
+            cmd.arg("--version");
+
+            #[cfg(target_os = "windows")]
+            cmd.creation_flags(CREATE_NO_WINDOW);
+
+            cmd.output().is_ok_and(|output| output.status.success())
+        })
+}
+
 impl Default for YtDlpManager {
     fn default() -> Self {
         Self::new().expect("Failed to create YtDlpManager")
     }
 }
+
+fn parse_os_release_field(contents: &str, key: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches('"').to_lowercase())
+    })
+}
+
+fn package_manager_command(distro: &str) -> Option<&'static str> {
+    match distro {
+        "debian" | "ubuntu" | "linuxmint" | "pop" => Some("sudo apt install ffmpeg"),
+        "fedora" | "rhel" | "centos" => Some("sudo dnf install ffmpeg"),
+        "arch" | "manjaro" | "endeavouros" => Some("sudo pacman -S ffmpeg"),
+        "opensuse" | "opensuse-tumbleweed" | "opensuse-leap" | "suse" => {
+            Some("sudo zypper install ffmpeg")
+        }
+        "alpine" => Some("sudo apk add ffmpeg"),
+        "void" => Some("sudo xbps-install ffmpeg"),
+        "gentoo" => Some("sudo emerge media-video/ffmpeg"),
+        "nixos" => Some("nix-env -iA nixpkgs.ffmpeg"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Excerpt of `yt-dlp --help` from 2024.12.23
+    const HELP: &str = "\
+Usage: yt-dlp [OPTIONS] URL [URL...]
+
+Options:
+  General Options:
+    -h, --help                      Print this help text and exit
+    --version                       Print program version and exit
+    -U, --update                    Update this program to the latest version
+    -i, --ignore-errors             Ignore download and postprocessing errors.
+
+  Network Options:
+    --proxy URL                     Use the specified HTTP/HTTPS/SOCKS proxy.
+
+  Geo-restriction:
+    --xff VALUE                     How to fake X-Forwarded-For HTTP header to
+                                    try bypassing geographic restriction. One of
+                                    \"default\" (only when known to be useful),
+                                    \"never\", an IP block in CIDR notation, or a
+                                    two-letter ISO 3166-2 country code
+
+  Video Selection:
+    -I, --playlist-items ITEM_SPEC  Comma separated playlist_index of the items
+                                    to download. Use negative indices to count
+                                    from the right and negative STEP to download
+                                    in reverse order. E.g. \"-I 1:3,7,-5::2\"
+
+  Download Options:
+    -r, --limit-rate RATE           Maximum download rate in bytes per second,
+                                    e.g. 50K or 4.2M
+    --no-overwrites                 Do not overwrite any files
+";
+
+    fn supported() -> HashSet<String> {
+        parse_help_flags(HELP)
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_long_options_from_help() {
+        let flags = supported();
+        assert!(flags.contains("--xff"));
+        assert!(flags.contains("--playlist-items"));
+        assert!(flags.contains("--limit-rate"));
+        assert!(!flags.contains("--geo-bypass"));
+        assert!(!flags.contains("--playlist-reverse"));
+    }
+
+    #[test]
+    fn keeps_supported_args() {
+        let args = strings(&["-f", "best", "--limit-rate", "2M", "--no-overwrites", "URL"]);
+        let (adapted, dropped) = adapt_args(args.clone(), &supported());
+        assert_eq!(adapted, args);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn maps_hidden_aliases_to_replacements() {
+        let (adapted, dropped) = adapt_args(
+            strings(&["--geo-bypass-country", "US", "--playlist-reverse", "URL"]),
+            &supported(),
+        );
+        assert_eq!(adapted, strings(&["--xff", "US", "--playlist-items", "::-1", "URL"]));
+        assert!(dropped.is_empty());
+
+        let (adapted, _) = adapt_args(strings(&["--geo-bypass", "URL"]), &supported());
+        assert_eq!(adapted, strings(&["--xff", "default", "URL"]));
+    }
+
+    #[test]
+    fn drops_unsupported_flags_with_their_values() {
+        let (adapted, dropped) = adapt_args(
+            strings(&["--trim-filenames", "40", "--restrict-filenames", "-f", "best", "URL"]),
+            &supported(),
+        );
+        assert_eq!(adapted, strings(&["-f", "best", "URL"]));
+        assert_eq!(dropped, strings(&["--trim-filenames", "--restrict-filenames"]));
+    }
+
+    #[test]
+    fn never_drops_the_url() {
+        let (adapted, dropped) =
+            adapt_args(strings(&["--restrict-filenames", "URL"]), &supported());
+        assert_eq!(adapted, strings(&["URL"]));
+        assert_eq!(dropped, strings(&["--restrict-filenames"]));
+    }
+
+    #[test]
+    fn drops_aliases_without_a_supported_replacement() {
+        let old = parse_help_flags("  --proxy URL  Use the specified proxy");
+        let (adapted, dropped) =
+            adapt_args(strings(&["--geo-bypass-country", "US", "URL"]), &old);
+        assert_eq!(adapted, strings(&["URL"]));
+        assert_eq!(dropped, strings(&["--geo-bypass-country"]));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::ytdlp::downloader::DownloadOptions;
+use crate::ytdlp::share::strip_credentials;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the manifest layout changes in a way consumers must handle.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: Option<u64>,
+}
+
+/// Records the parameters this app used for a download, unlike yt-dlp's
+/// info.json which describes the source media.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadManifest {
+    pub schema_version: u32,
+    pub source_url: String,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub options: DownloadOptions,
+    pub files: Vec<ManifestFile>,
+    pub created_at: String,
+}
+
+impl DownloadManifest {
+    pub fn new(
+        options: &DownloadOptions,
+        id: Option<String>,
+        title: Option<String>,
+        output_files: &[String],
+    ) -> Self {
+        // The manifest sits next to the media, which may well be shared
+        let mut options = options.clone();
+        strip_credentials(&mut options);
+
+        Self {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            source_url: options.url.clone(),
+            id,
+            title,
+            options,
+            files: output_files
+                .iter()
+                .map(|path| ManifestFile {
+                    path: path.clone(),
+                    size: std::fs::metadata(path).ok().map(|m| m.len()),
+                })
+                .collect(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Writes `<media name>.manifest.json` next to the first output file.
+    pub fn write(&self) -> std::io::Result<Option<PathBuf>> {
+        let Some(first) = self.files.first() else {
+            return Ok(None);
+        };
+
+        let media_path = Path::new(&first.path);
+        let stem = media_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "download".to_string());
+        let manifest_path = media_path.with_file_name(format!("{}.manifest.json", stem));
+
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&manifest_path, json)?;
+
+        Ok(Some(manifest_path))
+    }
+}
@@ -1,6 +1,18 @@
 pub mod manager;
 pub mod downloader;
 pub mod updater;
+pub mod duplicates;
+pub mod sponsorblock;
+pub mod settings;
+pub mod queue;
+pub mod power;
+pub mod manifest;
+pub mod disk;
+pub mod share;
+pub mod convert;
+pub mod proxy;
+pub mod history;
+pub mod thumbnails;
 
 pub use manager::YtDlpManager;
 pub use downloader::{DownloadOptions, DownloadProgress, Downloader};
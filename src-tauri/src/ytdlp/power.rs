@@ -0,0 +1,18 @@
+use starship_battery::{Manager, State};
+
+/// Whether the machine is currently running on battery.
+/// Returns `None` when there is no battery (desktops) or it can't be queried.
+pub fn is_on_battery() -> Option<bool> {
+    let manager = Manager::new().ok()?;
+
+    let mut has_battery = false;
+    let mut discharging = false;
+    for battery in manager.batteries().ok()?.flatten() {
+        has_battery = true;
+        if battery.state() == State::Discharging {
+            discharging = true;
+        }
+    }
+
+    has_battery.then_some(discharging)
+}
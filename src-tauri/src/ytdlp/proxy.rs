@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::error::Error as _;
+use std::time::{Duration, Instant};
+
+const PROXY_TEST_URL: &str = "https://www.youtube.com/generate_204";
+const PROXY_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyFailure {
+    InvalidUrl,
+    AuthRequired,
+    Unreachable,
+    Timeout,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTestResult {
+    pub success: bool,
+    pub latency_ms: Option<u64>,
+    pub failure: Option<ProxyFailure>,
+    pub error: Option<String>,
+}
+
+impl ProxyTestResult {
+    fn failed(failure: ProxyFailure, error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            latency_ms: None,
+            failure: Some(failure),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Accepts `http`, `https`, `socks5` and `socks5h` proxy URLs with a host.
+pub fn is_valid_proxy_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .map(|u| {
+            matches!(u.scheme(), "http" | "https" | "socks5" | "socks5h") && u.host_str().is_some()
+        })
+        .unwrap_or(false)
+}
+
+/// Makes one request through `proxy_url` to a YouTube endpoint and reports
+/// the round-trip latency or why it failed.
+pub async fn test_proxy(proxy_url: &str) -> ProxyTestResult {
+    if !is_valid_proxy_url(proxy_url) {
+        return ProxyTestResult::failed(
+            ProxyFailure::InvalidUrl,
+            "Proxy URL must use http, https, socks5 or socks5h",
+        );
+    }
+
+    let client = match reqwest::Proxy::all(proxy_url).and_then(|proxy| {
+        reqwest::Client::builder()
+            .proxy(proxy)
+            .timeout(PROXY_TEST_TIMEOUT)
+            .build()
+    }) {
+        Ok(client) => client,
+        Err(e) => return ProxyTestResult::failed(ProxyFailure::InvalidUrl, e.to_string()),
+    };
+
+    let started = Instant::now();
+    match client
+        .get(PROXY_TEST_URL)
+        .header("User-Agent", "yt-dlp-gui")
+        .send()
+        .await
+    {
+        Ok(response) if response.status() == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+            ProxyTestResult::failed(
+                ProxyFailure::AuthRequired,
+                "The proxy requires authentication",
+            )
+        }
+        Ok(response) if response.status().is_success() => ProxyTestResult {
+            success: true,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            failure: None,
+            error: None,
+        },
+        Ok(response) => ProxyTestResult::failed(
+            ProxyFailure::Other,
+            format!("Unexpected response: {}", response.status()),
+        ),
+        Err(e) => {
+            let message = error_chain(&e);
+            let failure = if message.contains("407") || message.to_lowercase().contains("auth") {
+                ProxyFailure::AuthRequired
+            } else if e.is_timeout() {
+                ProxyFailure::Timeout
+            } else if e.is_connect() {
+                ProxyFailure::Unreachable
+            } else {
+                ProxyFailure::Other
+            };
+            ProxyTestResult::failed(failure, message)
+        }
+    }
+}
+
+// reqwest's top-level message is generic; the cause says what actually went wrong
+fn error_chain(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
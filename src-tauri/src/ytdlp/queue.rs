@@ -0,0 +1,367 @@
+use crate::ytdlp::disk::atomic_write;
+use crate::ytdlp::downloader::DownloadOptions;
+use crate::ytdlp::manager::YtDlpManager;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+pub const DEFAULT_MAX_CONCURRENT: usize = 3;
+
+struct TrackedDownload {
+    options: DownloadOptions,
+    cancel: CancellationToken,
+    /// yt-dlp process id, once it has been spawned.
+    pid: Option<u32>,
+    last_activity: Instant,
+    stall_reported: bool,
+    tasks: Vec<AbortHandle>,
+    /// Set by `pause_download`: the cancel is a pause, not a stop.
+    pausing: bool,
+    /// Holds a slot; false while waiting for one.
+    active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueState {
+    pub max_concurrent: usize,
+    pub paused: bool,
+    pub active: Vec<String>,
+    pub queued: Vec<String>,
+    /// Downloads stopped with `pause_download`.
+    pub paused_downloads: Vec<String>,
+}
+
+/// Tracks queued and running downloads, limits how many run at once, and
+/// holds back downloads which haven't started yet while paused.
+pub struct DownloadQueue {
+    paused: watch::Sender<bool>,
+    slots: Arc<Semaphore>,
+    max_concurrent: Mutex<usize>,
+    downloads: Mutex<HashMap<String, TrackedDownload>>,
+    /// Options of downloads whose post-processing failed, kept for a retry.
+    postprocessing_failed: Mutex<HashMap<String, DownloadOptions>>,
+    /// Options of individually paused downloads, kept for `take_paused`.
+    paused_downloads: Mutex<HashMap<String, DownloadOptions>>,
+}
+
+impl DownloadQueue {
+    pub fn new() -> Self {
+        let (paused, _) = watch::channel(false);
+        Self {
+            paused,
+            slots: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT)),
+            max_concurrent: Mutex::new(DEFAULT_MAX_CONCURRENT),
+            downloads: Mutex::new(HashMap::new()),
+            postprocessing_failed: Mutex::new(HashMap::new()),
+            paused_downloads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Returns immediately when the queue isn't paused.
+    pub async fn wait_until_resumed(&self) {
+        let mut rx = self.paused.subscribe();
+        let _ = rx.wait_for(|paused| !*paused).await;
+    }
+
+    /// Waits until the queue isn't paused and a slot is free. The slot is held
+    /// until the returned permit is dropped.
+    pub async fn acquire_slot(&self) -> OwnedSemaphorePermit {
+        loop {
+            self.wait_until_resumed().await;
+            let permit = self
+                .slots
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("queue semaphore is never closed");
+            // Paused again while waiting for the slot
+            if !self.is_paused() {
+                return permit;
+            }
+        }
+    }
+
+    pub fn has_free_slot(&self) -> bool {
+        self.slots.available_permits() > 0
+    }
+
+    /// Changes how many downloads may run at once. Lowering it doesn't stop
+    /// running downloads; it takes effect as they finish.
+    pub fn set_max_concurrent(&self, max: usize) {
+        let max = max.max(1);
+        let mut current = self.max_concurrent.lock().unwrap();
+        if max > *current {
+            self.slots.add_permits(max - *current);
+        } else if max < *current {
+            let excess = *current - max;
+            let shortfall = excess - self.slots.forget_permits(excess);
+            if shortfall > 0 {
+                // Retire slots held by running downloads as they're released
+                let slots = self.slots.clone();
+                tokio::spawn(async move {
+                    for _ in 0..shortfall {
+                        if let Ok(permit) = slots.acquire().await {
+                            permit.forget();
+                        }
+                    }
+                });
+            }
+        }
+        *current = max;
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        *self.max_concurrent.lock().unwrap()
+    }
+
+    /// Marks a download as running once it holds a slot.
+    pub fn mark_active(&self, id: &str) {
+        if let Some(download) = self.downloads.lock().unwrap().get_mut(id) {
+            download.active = true;
+            download.last_activity = Instant::now();
+        }
+    }
+
+    pub fn state(&self) -> QueueState {
+        let downloads = self.downloads.lock().unwrap();
+        let (mut active, mut queued) = (Vec::new(), Vec::new());
+        for (id, download) in downloads.iter() {
+            if download.active {
+                active.push(id.clone());
+            } else {
+                queued.push(id.clone());
+            }
+        }
+
+        QueueState {
+            max_concurrent: self.max_concurrent(),
+            paused: self.is_paused(),
+            active,
+            queued,
+            paused_downloads: self.paused_downloads.lock().unwrap().keys().cloned().collect(),
+        }
+    }
+
+    /// Registers a download and returns the token that cancels it.
+    pub fn track(&self, id: &str, options: &DownloadOptions) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        self.downloads.lock().unwrap().insert(
+            id.to_string(),
+            TrackedDownload {
+                options: options.clone(),
+                cancel: cancel.clone(),
+                pid: None,
+                last_activity: Instant::now(),
+                stall_reported: false,
+                tasks: Vec::new(),
+                pausing: false,
+                active: false,
+            },
+        );
+        cancel
+    }
+
+    /// Registers a task to abort if the download is force-killed.
+    pub fn add_task(&self, id: &str, task: AbortHandle) {
+        if let Some(download) = self.downloads.lock().unwrap().get_mut(id) {
+            download.tasks.push(task);
+        }
+    }
+
+    /// Notes that a download made progress, remembering its process id if known.
+    pub fn record_activity(&self, id: &str, pid: Option<u32>) {
+        if let Some(download) = self.downloads.lock().unwrap().get_mut(id) {
+            download.last_activity = Instant::now();
+            download.stall_reported = false;
+            if pid.is_some() {
+                download.pid = pid;
+            }
+        }
+    }
+
+    /// Running downloads with no progress for `timeout` that haven't been
+    /// reported yet, with their idle time. Each stall is reported once.
+    pub fn take_stalled(&self, timeout: Duration) -> Vec<(String, Duration)> {
+        let mut downloads = self.downloads.lock().unwrap();
+        downloads
+            .iter_mut()
+            .filter(|(_, d)| d.pid.is_some() && !d.stall_reported)
+            .filter_map(|(id, d)| {
+                let idle = d.last_activity.elapsed();
+                (idle >= timeout).then(|| {
+                    d.stall_reported = true;
+                    (id.clone(), idle)
+                })
+            })
+            .collect()
+    }
+
+    /// Cancels a download, killing its yt-dlp process. Returns false if `id`
+    /// isn't tracked.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.downloads.lock().unwrap().get(id) {
+            Some(download) => {
+                download.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops a download like `cancel`, but keeps its options so `take_paused`
+    /// can pick it up again once its task has finished.
+    pub fn pause_download(&self, id: &str) -> bool {
+        match self.downloads.lock().unwrap().get_mut(id) {
+            Some(download) => {
+                download.pausing = true;
+                download.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Options of a download stopped by `pause_download`, removing it from the
+    /// paused set.
+    pub fn take_paused(&self, id: &str) -> Option<DownloadOptions> {
+        self.paused_downloads.lock().unwrap().remove(id)
+    }
+
+    /// Kills a download's process tree outright and aborts its tasks, for when
+    /// a normal cancel doesn't get through. Returns false if `id` isn't tracked.
+    pub fn force_kill(&self, id: &str) -> bool {
+        let Some(download) = self.downloads.lock().unwrap().remove(id) else {
+            return false;
+        };
+
+        download.cancel.cancel();
+        if let Some(pid) = download.pid {
+            kill_process_tree(pid);
+        }
+        for task in download.tasks {
+            task.abort();
+        }
+        true
+    }
+
+    /// Stops tracking a finished download. Returns true if it was paused, in
+    /// which case its options are kept for `take_paused`.
+    pub fn untrack(&self, id: &str) -> bool {
+        let Some(download) = self.downloads.lock().unwrap().remove(id) else {
+            return false;
+        };
+        if download.pausing {
+            self.paused_downloads
+                .lock()
+                .unwrap()
+                .insert(id.to_string(), download.options);
+        }
+        download.pausing
+    }
+
+    pub fn remember_postprocessing_failure(&self, id: &str, options: &DownloadOptions) {
+        self.postprocessing_failed
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), options.clone());
+    }
+
+    pub fn take_postprocessing_failure(&self, id: &str) -> Option<DownloadOptions> {
+        self.postprocessing_failed.lock().unwrap().remove(id)
+    }
+
+    pub fn tracked_count(&self) -> usize {
+        self.downloads.lock().unwrap().len()
+    }
+
+    /// Cancels every tracked download, returning how many were signalled.
+    pub fn cancel_all(&self) -> usize {
+        let downloads = self.downloads.lock().unwrap();
+        for download in downloads.values() {
+            download.cancel.cancel();
+        }
+        downloads.len()
+    }
+
+    /// Saves the options of all unfinished downloads so they aren't lost on exit.
+    pub fn persist(&self) -> std::io::Result<()> {
+        let mut pending: Vec<DownloadOptions> = self
+            .downloads
+            .lock()
+            .unwrap()
+            .values()
+            .map(|d| d.options.clone())
+            .collect();
+        pending.extend(self.paused_downloads.lock().unwrap().values().cloned());
+
+        let json = serde_json::to_string_pretty(&pending).map_err(std::io::Error::other)?;
+        atomic_write(&persisted_path()?, json)
+    }
+
+    /// Downloads saved by `persist` when the app last closed. The file is
+    /// removed, so they are only handed out once.
+    pub fn take_persisted() -> std::io::Result<Vec<DownloadOptions>> {
+        let path = persisted_path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        serde_json::from_str(&content).map_err(std::io::Error::other)
+    }
+}
+
+fn persisted_path() -> std::io::Result<PathBuf> {
+    let dir = YtDlpManager::get_app_data_dir().map_err(std::io::Error::other)?;
+    Ok(dir.join("queue.json"))
+}
+
+fn kill_process_tree(pid: u32) {
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.args(["/F", "/T", "/PID", &pid.to_string()]);
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        let _ = cmd.status();
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        // Children (ffmpeg) first, so they aren't reparented before we find them
+        let _ = std::process::Command::new("pkill")
+            .args(["-KILL", "-P", &pid.to_string()])
+            .status();
+        let _ = std::process::Command::new("kill")
+            .args(["-KILL", &pid.to_string()])
+            .status();
+    }
+}
+
+impl Default for DownloadQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::ytdlp::disk::atomic_write;
+use crate::ytdlp::manager::YtDlpManager;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Manager error: {0}")]
+    Manager(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Pause the download queue while the machine is running on battery.
+    pub pause_on_battery: bool,
+    /// Subtitle languages used when subtitles are requested without explicit languages.
+    pub default_subtitle_langs: Vec<String>,
+    /// Base URL of a GitHub proxy mirror used for yt-dlp release downloads and
+    /// the release API, for networks where GitHub is slow or blocked.
+    pub release_mirror_base: Option<String>,
+    /// Seconds without progress before a running download is reported as stalled.
+    pub stall_timeout_secs: Option<u64>,
+    /// Versioned yt-dlp binary selected with `switch_version`; `None` uses the default one.
+    pub active_ytdlp_version: Option<String>,
+    /// Proxy used for yt-dlp binary downloads and the release API.
+    pub release_proxy: Option<String>,
+    /// Downloads allowed to run at once; `None` uses `DEFAULT_MAX_CONCURRENT`.
+    pub max_concurrent_downloads: Option<usize>,
+    /// Output directory preselected in the UI; `None` uses the system download dir.
+    pub default_output_dir: Option<String>,
+    /// Same values as `StartDownloadRequest::video_quality`.
+    pub default_video_quality: Option<String>,
+    /// Same values as `StartDownloadRequest::audio_format`.
+    pub default_audio_format: Option<String>,
+    /// yt-dlp `--limit-rate` value, e.g. `2M`.
+    pub rate_limit: Option<String>,
+    pub embed_subs: bool,
+    pub embed_thumbnail: bool,
+    pub embed_metadata: bool,
+    /// Show an OS notification when a download finishes.
+    pub notifications_enabled: bool,
+}
+
+/// Accepts 2-3 letter language codes (optionally with a region/script suffix) or `all`.
+pub fn is_valid_subtitle_lang(lang: &str) -> bool {
+    if lang == "all" {
+        return true;
+    }
+
+    let mut parts = lang.splitn(2, '-');
+    let code = parts.next().unwrap_or("");
+    let code_ok = (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_lowercase());
+    let suffix_ok = parts
+        .next()
+        .map(|suffix| (2..=4).contains(&suffix.len()) && suffix.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or(true);
+
+    code_ok && suffix_ok
+}
+
+/// A mirror base must be an absolute http(s) URL without a query or fragment,
+/// since GitHub URLs are appended to it.
+pub fn is_valid_mirror_base(base: &str) -> bool {
+    reqwest::Url::parse(base)
+        .map(|url| {
+            matches!(url.scheme(), "http" | "https")
+                && url.host_str().is_some()
+                && url.query().is_none()
+                && url.fragment().is_none()
+        })
+        .unwrap_or(false)
+}
+
+impl Settings {
+    fn path() -> Result<PathBuf, SettingsError> {
+        let dir = YtDlpManager::get_app_data_dir()
+            .map_err(|e| SettingsError::Manager(e.to_string()))?;
+        Ok(dir.join("settings.json"))
+    }
+
+    pub fn load() -> Result<Self, SettingsError> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let path = Self::path()?;
+        atomic_write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
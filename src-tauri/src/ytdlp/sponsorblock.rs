@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SponsorBlockCategory {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+// Mirrors the categories accepted by yt-dlp's --sponsorblock-remove/--sponsorblock-mark
+pub const CATEGORIES: &[SponsorBlockCategory] = &[
+    SponsorBlockCategory {
+        id: "sponsor",
+        name: "Sponsor",
+        description: "Paid promotion, paid referrals and direct advertisements",
+    },
+    SponsorBlockCategory {
+        id: "selfpromo",
+        name: "Unpaid/Self Promotion",
+        description: "Promoting the creator's own merchandise, channels or donations",
+    },
+    SponsorBlockCategory {
+        id: "interaction",
+        name: "Interaction Reminder",
+        description: "Reminders to like, subscribe or follow in the middle of content",
+    },
+    SponsorBlockCategory {
+        id: "intro",
+        name: "Intro",
+        description: "Intro animations, pauses or openings without actual content",
+    },
+    SponsorBlockCategory {
+        id: "outro",
+        name: "Endcards/Credits",
+        description: "Credits, endcards or closing segments",
+    },
+    SponsorBlockCategory {
+        id: "preview",
+        name: "Preview/Recap",
+        description: "Previews of upcoming content or recaps of previous episodes",
+    },
+    SponsorBlockCategory {
+        id: "music_offtopic",
+        name: "Non-Music Section",
+        description: "Non-music parts of music videos",
+    },
+    SponsorBlockCategory {
+        id: "filler",
+        name: "Filler Tangent",
+        description: "Tangents and jokes not required to understand the main content",
+    },
+];
+
+pub fn is_known_category(id: &str) -> bool {
+    CATEGORIES.iter().any(|c| c.id == id)
+}
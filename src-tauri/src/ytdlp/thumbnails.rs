@@ -0,0 +1,82 @@
+use crate::ytdlp::disk::atomic_write;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Larger responses are not thumbnails
+const MAX_THUMBNAIL_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ThumbnailError {
+    #[error("Invalid thumbnail URL: {0}")]
+    InvalidUrl(String),
+    #[error("Failed to fetch thumbnail: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Not an image: {0}")]
+    NotAnImage(String),
+    #[error("Thumbnail is too large")]
+    TooLarge,
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Fetches thumbnail image bytes so the webview doesn't have to load the
+/// remote URL itself. Cached under the temp dir by `video_id`, or by URL when
+/// no id is given.
+pub async fn fetch_thumbnail(
+    client: &Client,
+    url: &str,
+    video_id: Option<&str>,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| ThumbnailError::InvalidUrl(url.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ThumbnailError::InvalidUrl(url.to_string()));
+    }
+
+    let path = cache_path(url, video_id);
+    if let Ok(bytes) = std::fs::read(&path) {
+        return Ok(bytes);
+    }
+
+    let response = client.get(parsed).send().await?.error_for_status()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if !content_type.starts_with("image/") {
+        return Err(ThumbnailError::NotAnImage(content_type));
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > MAX_THUMBNAIL_BYTES)
+    {
+        return Err(ThumbnailError::TooLarge);
+    }
+
+    let bytes = response.bytes().await?;
+    if bytes.len() > MAX_THUMBNAIL_BYTES {
+        return Err(ThumbnailError::TooLarge);
+    }
+
+    atomic_write(&path, &bytes)?;
+
+    Ok(bytes.to_vec())
+}
+
+/// `<video id>-<url hash>`, since one video has thumbnails of several sizes.
+fn cache_path(url: &str, video_id: Option<&str>) -> PathBuf {
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let key = match video_id.filter(|id| {
+        !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }) {
+        Some(id) => format!("{}-{}", id, &hash[..16]),
+        None => hash,
+    };
+    std::env::temp_dir()
+        .join("yt-dlp-gui")
+        .join("thumbnails")
+        .join(format!("{}.img", key))
+}
@@ -2,9 +2,17 @@ use crate::ytdlp::manager::YtDlpManager;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::io::Write;
 use std::path::PathBuf;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 #[derive(Error, Debug)]
 pub enum UpdaterError {
@@ -16,6 +24,18 @@ pub enum UpdaterError {
     ParseError,
     #[error("Manager error: {0}")]
     ManagerError(String),
+    #[error("Download cancelled")]
+    Cancelled,
+    #[error("No managed yt-dlp binary is installed")]
+    NoManagedBinary,
+    #[error("yt-dlp -U failed: {0}")]
+    SelfUpdateFailed(String),
+    #[error("No ffmpeg build is available for this platform")]
+    FfmpegUnavailable,
+    #[error("Failed to extract archive: {0}")]
+    ExtractError(String),
+    #[error("Checksum mismatch for downloaded file (expected {expected}, got {actual})")]
+    ChecksumMismatch { expected: String, actual: String },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,6 +53,13 @@ pub struct UpdateStatus {
     pub update_available: bool,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct SelfUpdateResult {
+    /// False when yt-dlp was already up to date.
+    pub updated: bool,
+    pub version: String,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct DownloadProgressEvent {
     pub downloaded: u64,
@@ -43,6 +70,8 @@ pub struct DownloadProgressEvent {
 pub struct Updater {
     client: Client,
     manager: YtDlpManager,
+    /// Proxy mirror that GitHub URLs are requested through as `{base}/{url}`.
+    mirror_base: Option<String>,
 }
 
 impl Updater {
@@ -51,15 +80,61 @@ impl Updater {
         Ok(Self {
             client: Client::new(),
             manager,
+            mirror_base: None,
         })
     }
 
-    pub async fn get_latest_version(&self) -> Result<VersionInfo, UpdaterError> {
-        let response = self
+    /// Like `new`, but sends every request through `proxy`.
+    pub fn with_proxy(proxy: &str) -> Result<Self, UpdaterError> {
+        let manager = YtDlpManager::new().map_err(|e| UpdaterError::ManagerError(e.to_string()))?;
+        let client = Client::builder()
+            .proxy(reqwest::Proxy::all(proxy)?)
+            .build()?;
+        Ok(Self {
+            client,
+            manager,
+            mirror_base: None,
+        })
+    }
+
+    pub fn set_mirror_base(&mut self, base: Option<String>) {
+        self.mirror_base = base;
+    }
+
+    /// GETs `url` through the release mirror when one is set, falling back to
+    /// the original URL if the mirror can't be reached or returns an error.
+    async fn get(&self, url: &str) -> Result<reqwest::Response, UpdaterError> {
+        let is_github =
+            url.starts_with("https://github.com/") || url.starts_with("https://api.github.com/");
+        let mirrored = self
+            .mirror_base
+            .as_ref()
+            .filter(|_| is_github)
+            .map(|base| format!("{}/{}", base.trim_end_matches('/'), url));
+
+        if let Some(mirrored) = mirrored {
+            let response = self
+                .client
+                .get(&mirrored)
+                .header("User-Agent", "yt-dlp-gui")
+                .send()
+                .await;
+            if let Ok(response) = response.and_then(|r| r.error_for_status()) {
+                return Ok(response);
+            }
+        }
+
+        Ok(self
             .client
-            .get("https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest")
+            .get(url)
             .header("User-Agent", "yt-dlp-gui")
             .send()
+            .await?)
+    }
+
+    pub async fn get_latest_version(&self) -> Result<VersionInfo, UpdaterError> {
+        let response = self
+            .get("https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest")
             .await?;
 
         let release: serde_json::Value = response.json().await?;
@@ -93,7 +168,7 @@ impl Updater {
         let latest_version = latest_info.map(|v| v.tag_name);
 
         let update_available = match (&current_version, &latest_version) {
-            (Some(current), Some(latest)) => current != latest,
+            (Some(current), Some(latest)) => is_newer(current, latest),
             (None, Some(_)) => true,
             _ => false,
         };
@@ -106,29 +181,192 @@ impl Updater {
         })
     }
 
-    pub async fn download_ytdlp<F>(&self, on_progress: F) -> Result<PathBuf, UpdaterError>
+    /// Updates the managed default binary in place with `yt-dlp -U`. Pinned
+    /// versioned binaries and a yt-dlp found on PATH are left alone.
+    pub async fn self_update(&self) -> Result<SelfUpdateResult, UpdaterError> {
+        let path = self.manager.get_default_ytdlp_path();
+        if !path.exists() {
+            return Err(UpdaterError::NoManagedBinary);
+        }
+
+        let mut cmd = tokio::process::Command::new(&path);
+        cmd.arg("-U");
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        let output = cmd.output().await?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr
+                .lines()
+                .chain(stdout.lines())
+                .find(|l| l.starts_with("ERROR:"))
+                .unwrap_or("unknown error");
+            return Err(UpdaterError::SelfUpdateFailed(message.to_string()));
+        }
+
+        let updated = stdout.lines().any(|l| l.starts_with("Updated yt-dlp to"));
+        let version = self
+            .manager
+            .get_ytdlp_version()
+            .map_err(|e| UpdaterError::ManagerError(e.to_string()))?;
+        Ok(SelfUpdateResult { updated, version })
+    }
+
+    /// Downloads the yt-dlp binary. Cancelling `cancel` aborts the stream and
+    /// removes the partial `.tmp` file.
+    pub async fn download_ytdlp<F>(
+        &self,
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<PathBuf, UpdaterError>
     where
         F: Fn(DownloadProgressEvent),
     {
         let (url, filename) = YtDlpManager::get_download_url();
         let dest_path = self.manager.get_bin_dir().join(filename);
+        let expected = self.fetch_release_checksum(url).await?;
+        self.download_binary(url, dest_path, Some(&expected), cancel, on_progress)
+            .await
+    }
 
+    /// Downloads release `tag` as a versioned binary next to the default one.
+    pub async fn download_ytdlp_version<F>(
+        &self,
+        tag: &str,
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<PathBuf, UpdaterError>
+    where
+        F: Fn(DownloadProgressEvent),
+    {
+        if !crate::ytdlp::manager::is_valid_version_tag(tag) {
+            return Err(UpdaterError::ParseError);
+        }
+
+        // Same asset as the latest release, from the tagged release instead
+        let (latest_url, _) = YtDlpManager::get_download_url();
+        let asset = latest_url.rsplit('/').next().unwrap_or("yt-dlp");
+        let url = format!(
+            "https://github.com/yt-dlp/yt-dlp/releases/download/{}/{}",
+            tag, asset
+        );
+        let dest_path = self.manager.get_versioned_ytdlp_path(tag);
+        let expected = self.fetch_release_checksum(&url).await?;
+        self.download_binary(&url, dest_path, Some(&expected), cancel, on_progress)
+            .await
+    }
+
+    /// Downloads the platform's static ffmpeg build and installs `ffmpeg` and
+    /// `ffprobe` into the bin dir. Cancelling `cancel` aborts the download.
+    pub async fn download_ffmpeg<F>(
+        &self,
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<PathBuf, UpdaterError>
+    where
+        F: Fn(DownloadProgressEvent),
+    {
+        let url = YtDlpManager::get_ffmpeg_download_url().ok_or(UpdaterError::FfmpegUnavailable)?;
+        let bin_dir = self.manager.get_bin_dir();
+        let archive = bin_dir.join("ffmpeg-archive.tmp");
+
+        let result = self
+            .stream_to_file(url, &archive, &cancel, on_progress)
+            .await;
+        if result.is_err() {
+            let _ = std::fs::remove_file(&archive);
+        }
+        result?;
+
+        let staging = bin_dir.join("ffmpeg-extract");
+        let extracted = extract_ffmpeg(&archive, url, &staging, bin_dir).await;
+        let _ = std::fs::remove_dir_all(&staging);
+        let _ = std::fs::remove_file(&archive);
+        extracted?;
+
+        Ok(self.manager.get_ffmpeg_path())
+    }
+
+    /// SHA-256 of a release asset, from the `SHA2-256SUMS` file published
+    /// next to it.
+    async fn fetch_release_checksum(&self, asset_url: &str) -> Result<String, UpdaterError> {
+        let (base, asset) = asset_url.rsplit_once('/').ok_or(UpdaterError::ParseError)?;
+        let sums = self
+            .get(&format!("{}/SHA2-256SUMS", base))
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        find_checksum(&sums, asset).ok_or(UpdaterError::ParseError)
+    }
+
+    async fn download_binary<F>(
+        &self,
+        url: &str,
+        dest_path: PathBuf,
+        expected_sha256: Option<&str>,
+        cancel: CancellationToken,
+        on_progress: F,
+    ) -> Result<PathBuf, UpdaterError>
+    where
+        F: Fn(DownloadProgressEvent),
+    {
         // Create temp file
         let temp_path = dest_path.with_extension("tmp");
 
-        let response = self
-            .client
-            .get(url)
-            .header("User-Agent", "yt-dlp-gui")
-            .send()
-            .await?;
+        let mut result = self
+            .stream_to_file(url, &temp_path, &cancel, on_progress)
+            .await;
+        if let (Ok(()), Some(expected)) = (&result, expected_sha256) {
+            result = verify_sha256(&temp_path, expected);
+        }
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        result?;
+
+        // Move temp file to final location
+        std::fs::rename(&temp_path, &dest_path)?;
+
+        // Make executable on Unix
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&dest_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&dest_path, perms)?;
+        }
+
+        Ok(dest_path)
+    }
+
+    async fn stream_to_file<F>(
+        &self,
+        url: &str,
+        temp_path: &std::path::Path,
+        cancel: &CancellationToken,
+        on_progress: F,
+    ) -> Result<(), UpdaterError>
+    where
+        F: Fn(DownloadProgressEvent),
+    {
+        let response = self.get(url).await?;
 
         let total_size = response.content_length();
         let mut downloaded: u64 = 0;
-        let mut file = std::fs::File::create(&temp_path)?;
+        let mut file = std::fs::File::create(temp_path)?;
         let mut stream = response.bytes_stream();
 
-        while let Some(chunk) = stream.next().await {
+        loop {
+            let chunk = tokio::select! {
+                chunk = stream.next() => chunk,
+                _ = cancel.cancelled() => return Err(UpdaterError::Cancelled),
+            };
+            let Some(chunk) = chunk else { break };
             let chunk = chunk?;
             file.write_all(&chunk)?;
             downloaded += chunk.len() as u64;
@@ -141,26 +379,170 @@ impl Updater {
         }
 
         // Flush and close file
-        drop(file);
+        file.flush()?;
+        Ok(())
+    }
 
-        // Move temp file to final location
-        std::fs::rename(&temp_path, &dest_path)?;
+    pub fn get_manager(&self) -> &YtDlpManager {
+        &self.manager
+    }
+}
+
+// Unpacks `archive` into `staging`, then moves the ffmpeg and ffprobe binaries
+// (wherever they are nested) into `bin_dir`.
+async fn extract_ffmpeg(
+    archive: &std::path::Path,
+    url: &str,
+    staging: &std::path::Path,
+    bin_dir: &std::path::Path,
+) -> Result<(), UpdaterError> {
+    let _ = std::fs::remove_dir_all(staging);
+    std::fs::create_dir_all(staging)?;
+
+    if url.ends_with(".tar.xz") {
+        // tar with xz support ships with every mainstream Linux distribution
+        let output = tokio::process::Command::new("tar")
+            .arg("-xJf")
+            .arg(archive)
+            .arg("-C")
+            .arg(staging)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(UpdaterError::ExtractError(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+    } else {
+        // The Windows and macOS builds are zips
+        let archive = archive.to_path_buf();
+        let staging = staging.to_path_buf();
+        tokio::task::spawn_blocking(move || extract_zip_binaries(&archive, &staging))
+            .await
+            .map_err(|e| UpdaterError::ExtractError(e.to_string()))??;
+    }
+
+    for name in FFMPEG_BINARIES {
+        let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+        let Some(found) = find_file(staging, &file_name) else {
+            // ffprobe is optional; some builds ship ffmpeg alone
+            if name == "ffmpeg" {
+                return Err(UpdaterError::ExtractError(format!(
+                    "{} not found in archive",
+                    file_name
+                )));
+            }
+            continue;
+        };
+
+        let dest = bin_dir.join(&file_name);
+        std::fs::rename(&found, &dest)?;
 
-        // Make executable on Unix
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = std::fs::metadata(&dest_path)?.permissions();
-            perms.set_mode(0o755);
-            std::fs::set_permissions(&dest_path, perms)?;
+            std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o755))?;
         }
+    }
+    Ok(())
+}
 
-        Ok(dest_path)
+const FFMPEG_BINARIES: [&str; 2] = ["ffmpeg", "ffprobe"];
+
+// Writes the ffmpeg/ffprobe entries of a zip, from whichever folder they sit
+// in, flat into `staging`. Other entries are skipped.
+fn extract_zip_binaries(
+    archive: &std::path::Path,
+    staging: &std::path::Path,
+) -> Result<(), UpdaterError> {
+    let file = std::fs::File::open(archive)?;
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| UpdaterError::ExtractError(e.to_string()))?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| UpdaterError::ExtractError(e.to_string()))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(file_name) = entry
+            .enclosed_name()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        else {
+            continue;
+        };
+        let wanted = FFMPEG_BINARIES
+            .iter()
+            .any(|name| file_name == format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+        if wanted {
+            let mut out = std::fs::File::create(staging.join(&file_name))?;
+            std::io::copy(&mut entry, &mut out)?;
+        }
     }
+    Ok(())
+}
 
-    pub fn get_manager(&self) -> &YtDlpManager {
-        &self.manager
+fn find_file(dir: &std::path::Path, file_name: &str) -> Option<PathBuf> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, file_name) {
+                return Some(found);
+            }
+        } else if path.file_name().is_some_and(|n| n == file_name) {
+            return Some(path);
+        }
     }
+    None
+}
+
+/// Digest of `asset` in a `SHA2-256SUMS` file, whose lines look like
+/// `<hex digest>  <file name>`, with `*<file name>` for binary mode.
+fn find_checksum(sums: &str, asset: &str) -> Option<String> {
+    sums.lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == asset)
+        .map(|(digest, _)| digest.to_ascii_lowercase())
+}
+
+fn verify_sha256(path: &std::path::Path, expected: &str) -> Result<(), UpdaterError> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected {
+        return Err(UpdaterError::ChecksumMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Whether date-based version `latest` (e.g. `2024.03.10.1`) is strictly newer
+/// than `current`. Missing trailing components count as zero; a version that
+/// doesn't parse never counts as newer.
+fn is_newer(current: &str, latest: &str) -> bool {
+    let parse = |v: &str| -> Option<Vec<u64>> {
+        v.trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect()
+    };
+    let (Some(current), Some(latest)) = (parse(current), parse(latest)) else {
+        return false;
+    };
+
+    let len = current.len().max(latest.len());
+    let component = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    for i in 0..len {
+        match component(&latest, i).cmp(&component(&current, i)) {
+            std::cmp::Ordering::Equal => continue,
+            ordering => return ordering == std::cmp::Ordering::Greater,
+        }
+    }
+    false
 }
 
 impl Default for Updater {
@@ -168,3 +550,65 @@ impl Default for Updater {
         Self::new().expect("Failed to create Updater")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_versions_are_not_newer() {
+        assert!(!is_newer("2024.03.10", "2024.03.10"));
+        assert!(!is_newer("v2024.03.10", "2024.03.10"));
+    }
+
+    #[test]
+    fn extra_component_is_newer() {
+        assert!(is_newer("2024.03.10", "2024.03.10.1"));
+        assert!(!is_newer("2024.03.10.1", "2024.03.10"));
+    }
+
+    #[test]
+    fn compares_components_numerically() {
+        assert!(is_newer("2024.03.10", "2024.03.11"));
+        assert!(is_newer("2024.09.27", "2024.10.07"));
+        assert!(is_newer("2023.12.30", "2024.01.01"));
+        assert!(!is_newer("2024.10.07", "2024.09.27"));
+    }
+
+    #[test]
+    fn verifies_known_digest() {
+        let path = std::env::temp_dir().join(format!("yt-dlp-gui-sha-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"hello world").unwrap();
+
+        let ok = verify_sha256(
+            &path,
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+        );
+        let mismatch = verify_sha256(&path, &"0".repeat(64));
+        let _ = std::fs::remove_file(&path);
+
+        assert!(ok.is_ok());
+        assert!(matches!(mismatch, Err(UpdaterError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn finds_checksums_in_sums_file() {
+        let sums = "\
+1111111111111111111111111111111111111111111111111111111111111111  yt-dlp
+2222222222222222222222222222222222222222222222222222222222222222 *yt-dlp.exe
+AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA  yt-dlp_macos
+";
+        assert_eq!(find_checksum(sums, "yt-dlp"), Some("1".repeat(64)));
+        assert_eq!(find_checksum(sums, "yt-dlp.exe"), Some("2".repeat(64)));
+        assert_eq!(find_checksum(sums, "yt-dlp_macos"), Some("a".repeat(64)));
+        assert_eq!(find_checksum(sums, "yt-dlp_linux"), None);
+    }
+
+    #[test]
+    fn malformed_versions_are_never_newer() {
+        assert!(!is_newer("2024.03.10", "nightly"));
+        assert!(!is_newer("garbage", "2024.03.10"));
+        assert!(!is_newer("2024.03.10", "2024..11"));
+        assert!(!is_newer("2024.03.10", ""));
+    }
+}